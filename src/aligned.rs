@@ -0,0 +1,98 @@
+//! Buffers aligned for SIMD loads.
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+
+/// Alignment in bytes of an [AlignedVec], matching the width of AVX registers.
+pub const ALIGNMENT: usize = 32;
+
+/// Fixed-length buffer whose storage is aligned to at least [ALIGNMENT] bytes.
+pub struct AlignedVec<T: Copy + Default> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+impl<T: Copy + Default> AlignedVec<T> {
+    /// Constructs a buffer of `len` default values.
+    pub fn new(len: usize) -> Self {
+        let layout = Self::layout(len);
+        if layout.size() == 0 {
+            // Zero-sized buffers never touch memory but still report an aligned address.
+            let ptr = NonNull::new(layout.align() as *mut T).expect("alignment is non-zero");
+            return AlignedVec { ptr, len };
+        }
+        let raw = unsafe { alloc::alloc(layout) } as *mut T;
+        let ptr = match NonNull::new(raw) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(layout),
+        };
+        for i in 0..len {
+            unsafe { ptr.as_ptr().add(i).write(T::default()) };
+        }
+        AlignedVec { ptr, len }
+    }
+
+    fn layout(len: usize) -> Layout {
+        let align = ALIGNMENT.max(mem::align_of::<T>());
+        let size = mem::size_of::<T>()
+            .checked_mul(len)
+            .expect("buffer size overflows usize");
+        Layout::from_size_align(size, align).expect("invalid buffer layout")
+    }
+}
+
+impl<T: Copy + Default> Drop for AlignedVec<T> {
+    fn drop(&mut self) {
+        let layout = Self::layout(self.len);
+        if layout.size() != 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout) };
+        }
+    }
+}
+
+impl<T: Copy + Default> Deref for AlignedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy + Default> DerefMut for AlignedVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy + Default + fmt::Debug> fmt::Debug for AlignedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+unsafe impl<T: Copy + Default + Send> Send for AlignedVec<T> {}
+unsafe impl<T: Copy + Default + Sync> Sync for AlignedVec<T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::aligned::{AlignedVec, ALIGNMENT};
+
+    #[test]
+    fn new() {
+        let buffer: AlignedVec<f64> = AlignedVec::new(13);
+        assert_eq!(buffer.len(), 13);
+        assert_eq!(buffer.as_ptr() as usize % ALIGNMENT, 0);
+        assert!(buffer.iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn new_empty() {
+        let buffer: AlignedVec<f64> = AlignedVec::new(0);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr() as usize % ALIGNMENT, 0);
+    }
+}
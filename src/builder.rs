@@ -0,0 +1,83 @@
+//! Construction of functionals with optional settings.
+
+use crate::error::FunctionalError;
+use crate::functional::{Functional, Polarization};
+
+/// Builder of a [Functional] which validates optional settings before construction.
+#[derive(Clone, Debug)]
+pub struct FunctionalBuilder {
+    id: i32,
+    polarization: Polarization,
+    dimension: Option<u8>,
+}
+
+impl FunctionalBuilder {
+    /// Constructs a builder of the unpolarized functional with the given id.
+    pub fn new(id: i32) -> Self {
+        FunctionalBuilder {
+            id,
+            polarization: Polarization::Unpolarized,
+            dimension: None,
+        }
+    }
+
+    /// Sets the polarization of the functional.
+    pub fn polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    /// Requires the functional to be defined in `dim` spatial dimensions.
+    ///
+    /// LibXC fixes the dimension of a functional by its id, so the requested dimension is
+    /// checked against the functional rather than passed to LibXC.
+    pub fn dimension(mut self, dim: u8) -> Self {
+        self.dimension = Some(dim);
+        self
+    }
+
+    /// Constructs the functional.
+    pub fn build(self) -> Result<Functional, FunctionalError> {
+        let func = Functional::from_id(self.id, self.polarization)?;
+        match self.dimension {
+            Some(dim) if !func.supports_dimension(dim) => {
+                Err(FunctionalError::UnsupportedDimension(dim))
+            }
+            _ => Ok(func),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::FunctionalBuilder;
+    use crate::functional::Polarization;
+    use crate::ids;
+
+    #[test]
+    fn build() {
+        let func = FunctionalBuilder::new(ids::GGA_X_PBE)
+            .polarization(Polarization::Polarized)
+            .build()
+            .unwrap();
+        assert_eq!(func.number(), ids::GGA_X_PBE);
+        assert_eq!(func.polarization(), Polarization::Polarized);
+    }
+
+    #[test]
+    fn dimension() {
+        let func = FunctionalBuilder::new(ids::LDA_X_2D)
+            .dimension(2)
+            .build()
+            .unwrap();
+        assert!(func.supports_dimension(2));
+        let rho = [0.1, 0.2, 0.3];
+        let exc = func.lda_exc(&rho);
+        assert_eq!(exc.len(), rho.len());
+        assert!(exc.iter().all(|e| *e < 0.0));
+        assert!(FunctionalBuilder::new(ids::LDA_X_2D)
+            .dimension(3)
+            .build()
+            .is_err());
+    }
+}
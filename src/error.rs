@@ -1,11 +1,92 @@
+use std::io;
+
 use thiserror::Error;
 
+use crate::functional::{FunctionalFamily, Polarization};
+
 #[derive(Error, Debug)]
 pub enum FunctionalError {
-    #[error("failed to initialize: error code {0}")]
+    #[error("failed to initialize: error code {0} ({})", describe_init_code(*.0))]
     FailedInitialization(i32),
     #[error("invalid functional ID")]
     InvalidID,
     #[error("invalid functional name")]
     InvalidName,
+    #[error("invalid functional kind: {0}")]
+    InvalidKind(i32),
+    #[error("invalid functional family: {0}")]
+    InvalidFamily(i32),
+    #[error("invalid number of spin channels: {0}")]
+    InvalidNspin(u32),
+    #[error("invalid external parameter index or count: {0}")]
+    InvalidExtParam(i32),
+    #[error("functional is not defined in {0} dimensions")]
+    UnsupportedDimension(u8),
+    #[error("functional cannot be initialized as {0}")]
+    UnsupportedPolarization(Polarization),
+    #[error("functional is not initialized")]
+    Uninitialized,
+    #[error("functional cannot be evaluated as {0}")]
+    UnsupportedFamily(FunctionalFamily),
+    #[error("functional does not provide this derivative")]
+    UnsupportedDerivative,
+    #[error("invalid string returned by LibXC")]
+    InvalidString,
+    #[error("invalid version returned by LibXC")]
+    InvalidVersion,
+    #[error("LibXC produced a non-finite output at index {0}")]
+    NonFiniteOutput(usize),
+    #[error("no functionals to combine")]
+    NoFunctionals,
+    #[error("no points to evaluate")]
+    NoPoints,
+    #[error("functionals cannot be evaluated on the same inputs")]
+    IncompatibleFunctionals,
+}
+
+/// Returns a description of an error code returned by LibXC when initializing a functional.
+pub fn describe_init_code(code: i32) -> &'static str {
+    match code {
+        0 => "success",
+        -1 => "unknown functional",
+        -2 => "functional does not belong to a known family",
+        _ => "unrecognized error code",
+    }
+}
+
+impl From<FunctionalError> for io::Error {
+    fn from(err: FunctionalError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::error::{describe_init_code, FunctionalError};
+    use crate::functional::Polarization;
+
+    #[test]
+    fn describe_init_code_known() {
+        assert!(!describe_init_code(-2).is_empty());
+        let err = FunctionalError::FailedInitialization(-2);
+        assert!(err.to_string().contains(describe_init_code(-2)));
+    }
+
+    #[test]
+    fn unsupported_polarization() {
+        let err = FunctionalError::UnsupportedPolarization(Polarization::Polarized);
+        assert_eq!(
+            err.to_string(),
+            "functional cannot be initialized as Polarized"
+        );
+    }
+
+    #[test]
+    fn into_io_error() {
+        let err: io::Error = FunctionalError::InvalidName.into();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "invalid functional name");
+    }
 }
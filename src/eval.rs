@@ -0,0 +1,327 @@
+//! Evaluation of functionals of any family through a single interface.
+//!
+//! Inputs and outputs follow the layouts documented in the [lda](crate::lda),
+//! [gga](crate::gga), and [mgga](crate::mgga) modules.
+
+use std::time::Instant;
+
+use crate::error::FunctionalError;
+use crate::flags;
+use crate::functional::{Functional, FunctionalFamily, Polarization, Spin};
+
+/// Input arrays of an evaluation.
+///
+/// Arrays which are not read by the functional being evaluated may be left empty.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EvalInputs<'a> {
+    pub rho: &'a [f64],
+    pub sigma: &'a [f64],
+    pub lapl: &'a [f64],
+    pub tau: &'a [f64],
+}
+
+/// Energy per particle and first derivatives produced by an evaluation.
+///
+/// Derivatives with respect to arrays which are not read by the functional are empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvalOutputs {
+    pub zk: Vec<f64>,
+    pub vrho: Vec<f64>,
+    pub vsigma: Vec<f64>,
+    pub vlapl: Vec<f64>,
+    pub vtau: Vec<f64>,
+}
+
+impl Functional {
+    /// Evaluates the energy per particle and its first derivatives with the evaluator matching
+    /// the family of the functional.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the functional does not belong to the LDA, GGA, or MGGA families or does not
+    /// provide its potential. [Functional::try_evaluate] reports these cases as errors.
+    pub fn evaluate(&self, inputs: &EvalInputs) -> EvalOutputs {
+        match self.base_family() {
+            Some(FunctionalFamily::LDA) => {
+                let (zk, vrho) = self.lda_exc_vxc(inputs.rho);
+                EvalOutputs {
+                    zk,
+                    vrho,
+                    ..Default::default()
+                }
+            }
+            Some(FunctionalFamily::GGA) => {
+                let (zk, vrho, vsigma) = self.gga_exc_vxc(inputs.rho, inputs.sigma);
+                EvalOutputs {
+                    zk,
+                    vrho,
+                    vsigma,
+                    ..Default::default()
+                }
+            }
+            Some(FunctionalFamily::MGGA) => {
+                let (zk, vrho, vsigma, vlapl, vtau) =
+                    self.mgga_exc_vxc(inputs.rho, inputs.sigma, inputs.lapl, inputs.tau);
+                EvalOutputs {
+                    zk,
+                    vrho,
+                    vsigma,
+                    vlapl,
+                    vtau,
+                }
+            }
+            _ => panic!("functional has no LDA, GGA, or MGGA evaluator"),
+        }
+    }
+
+    /// Evaluates the energy per particle and its first derivatives, failing instead of panicking
+    /// if the functional is uninitialized or cannot be evaluated through the LDA, GGA, or MGGA
+    /// interfaces.
+    pub fn try_evaluate(&self, inputs: &EvalInputs) -> Result<EvalOutputs, FunctionalError> {
+        match self.base_family() {
+            Some(family) => {
+                self.check_evaluator(family, flags::HAVE_VXC)?;
+                Ok(self.evaluate(inputs))
+            }
+            None if self.xc_func.is_null() => Err(FunctionalError::Uninitialized),
+            None => Err(FunctionalError::UnsupportedFamily(
+                self.family().unwrap_or(FunctionalFamily::Unknown),
+            )),
+        }
+    }
+
+    /// Returns true if the functional produces a nonzero potential in `channel` at a
+    /// representative spin-polarized point.
+    ///
+    /// Unpolarized functionals are evaluated on the total density and contribute to both channels
+    /// alike. Functionals which cannot be evaluated through [Functional::evaluate] contribute to
+    /// neither channel.
+    pub fn channel_contributes(&self, channel: Spin) -> bool {
+        if self.base_family().is_none() || !self.has_flag(flags::HAVE_VXC) {
+            return false;
+        }
+        let (rho, sigma, lapl, tau): (&[f64], &[f64], &[f64], &[f64]) = match self.polarization {
+            Polarization::Unpolarized => (&[0.3], &[0.05], &[0.0], &[0.15]),
+            Polarization::Polarized => {
+                (&[0.2, 0.1], &[0.02, 0.01, 0.015], &[0.0, 0.0], &[0.1, 0.05])
+            }
+        };
+        let inputs = EvalInputs {
+            rho,
+            sigma,
+            lapl,
+            tau,
+        };
+        let vrho = self.evaluate(&inputs).vrho;
+        let index = match (self.polarization, channel) {
+            (Polarization::Polarized, Spin::Down) => 1,
+            _ => 0,
+        };
+        vrho[index] != 0.0
+    }
+
+    /// Returns true if the functional evaluates to finite values at zero density with its default
+    /// density threshold.
+    pub fn handles_zero_density(&self) -> bool {
+        if self.base_family().is_none() {
+            return false;
+        }
+        // Probe a copy so that a threshold set on this functional is left in place.
+        let mut func = self.clone();
+        let default = unsafe { (*libxc_sys::xc_func_get_info(self.xc_func)).dens_threshold };
+        func.set_dens_threshold(default);
+        let layout = func.input_layout();
+        let rho = vec![0.0; layout.rho];
+        let sigma = vec![0.0; layout.sigma];
+        let lapl = vec![0.0; layout.lapl];
+        let tau = vec![0.0; layout.tau];
+        let inputs = EvalInputs {
+            rho: &rho,
+            sigma: &sigma,
+            lapl: &lapl,
+            tau: &tau,
+        };
+        let outputs = func.evaluate(&inputs);
+        [
+            outputs.zk,
+            outputs.vrho,
+            outputs.vsigma,
+            outputs.vlapl,
+            outputs.vtau,
+        ]
+        .iter()
+        .flatten()
+        .all(|value| value.is_finite())
+    }
+
+    /// Evaluates the functional on a synthetic grid of `npoints` points and returns the number of
+    /// points evaluated per second.
+    ///
+    /// Functionals without a potential are timed on their energy alone. Elapsed times below the
+    /// one nanosecond resolution of [Instant] are rounded up to it, so the result is always finite.
+    pub fn throughput(&self, npoints: usize) -> Result<f64, FunctionalError> {
+        if npoints == 0 {
+            return Err(FunctionalError::NoPoints);
+        }
+        let layout = self.input_layout();
+        let synthetic = |components: usize, value: f64| vec![value; npoints * components];
+        let rho = synthetic(layout.rho, 0.1);
+        let sigma = synthetic(layout.sigma, 0.01);
+        let lapl = synthetic(layout.lapl, 0.0);
+        let tau = synthetic(layout.tau, 0.1);
+        let inputs = EvalInputs {
+            rho: &rho,
+            sigma: &sigma,
+            lapl: &lapl,
+            tau: &tau,
+        };
+        let start = Instant::now();
+        match self.base_family() {
+            Some(_) if !self.has_flag(flags::HAVE_VXC) => {
+                self.try_evaluate_exc(&inputs)?;
+            }
+            _ => {
+                self.try_evaluate(&inputs)?;
+            }
+        }
+        Ok(npoints as f64 / start.elapsed().as_secs_f64().max(1e-9))
+    }
+
+    // Evaluates the energy per particle alone with the evaluator matching the family of the
+    // functional.
+    fn try_evaluate_exc(&self, inputs: &EvalInputs) -> Result<Vec<f64>, FunctionalError> {
+        match self.base_family() {
+            Some(FunctionalFamily::LDA) => self.try_lda_exc(inputs.rho),
+            Some(FunctionalFamily::GGA) => self.try_gga_exc(inputs.rho, inputs.sigma),
+            _ => self.try_mgga_exc(inputs.rho, inputs.sigma, inputs.lapl, inputs.tau),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::FunctionalError;
+    use crate::eval::EvalInputs;
+    use crate::flags;
+    use crate::functional::{Functional, Polarization, Spin};
+    use crate::ids;
+    use crate::util;
+
+    #[test]
+    fn channel_contributes() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap();
+        assert!(func.channel_contributes(Spin::Up));
+        assert!(func.channel_contributes(Spin::Down));
+        let func = Functional::from_id(ids::LDA_C_PW, Polarization::Unpolarized).unwrap();
+        assert!(func.channel_contributes(Spin::Down));
+        let func = Functional {
+            xc_func: std::ptr::null_mut(),
+            xc_info: std::ptr::null(),
+            id: ids::LDA_X,
+            polarization: Polarization::Unpolarized,
+            ext_params: Vec::new(),
+            dens_threshold: 0.0,
+        };
+        assert!(!func.channel_contributes(Spin::Up));
+    }
+
+    #[test]
+    fn handles_zero_density() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(func.handles_zero_density());
+        let func = Functional::from_id(ids::LDA_C_PW, Polarization::Polarized).unwrap();
+        assert!(func.handles_zero_density());
+    }
+
+    #[test]
+    fn throughput() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap();
+        assert!(func.throughput(10_000).unwrap() > 0.0);
+        assert!(func.throughput(1).unwrap().is_finite());
+    }
+
+    #[test]
+    fn throughput_empty() {
+        let func = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        match func.throughput(0) {
+            Err(FunctionalError::NoPoints) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn throughput_exc_only() {
+        // Time every functional of the linked LibXC which provides an energy but no potential.
+        for id in util::available_functional_numbers() {
+            let func = match Functional::from_id(id, Polarization::Unpolarized) {
+                Ok(func) => func,
+                Err(_) => continue,
+            };
+            let exc_only = func.has_flag(flags::HAVE_EXC) && !func.has_flag(flags::HAVE_VXC);
+            if func.base_family().is_some() && exc_only {
+                assert!(func.throughput(16).unwrap() > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate() {
+        let rho = [0.1, 0.2];
+        let sigma = [0.01, 0.02];
+        let lapl = [0.0];
+        let inputs = EvalInputs {
+            rho: &rho,
+            sigma: &sigma,
+            lapl: &lapl,
+            ..Default::default()
+        };
+        let lda = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        let outputs = lda.evaluate(&inputs);
+        assert_eq!(outputs.zk, lda.lda_exc(&rho));
+        assert_eq!(outputs.vrho, lda.lda_vxc(&rho));
+        assert!(outputs.vsigma.is_empty());
+        let gga = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        let outputs = gga.evaluate(&inputs);
+        assert_eq!(outputs.zk, gga.gga_exc(&rho, &sigma));
+        assert_eq!(outputs.vsigma, gga.gga_vxc(&rho, &sigma).1);
+        assert!(outputs.vlapl.is_empty());
+        assert!(outputs.vtau.is_empty());
+    }
+
+    #[test]
+    fn try_evaluate() {
+        let rho = [0.1, 0.2];
+        let inputs = EvalInputs {
+            rho: &rho,
+            ..Default::default()
+        };
+        let lda = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        assert_eq!(lda.try_evaluate(&inputs).unwrap(), lda.evaluate(&inputs));
+        let func = Functional {
+            xc_func: std::ptr::null_mut(),
+            xc_info: std::ptr::null(),
+            id: ids::LDA_X,
+            polarization: Polarization::Unpolarized,
+            ext_params: Vec::new(),
+            dens_threshold: 0.0,
+        };
+        assert!(matches!(
+            func.try_evaluate(&inputs),
+            Err(FunctionalError::Uninitialized)
+        ));
+    }
+
+    #[test]
+    fn evaluate_mgga_without_lapl() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let (rho, sigma, tau) = ([0.1, 0.2], [0.01, 0.02], [0.05, 0.1]);
+        let inputs = EvalInputs {
+            rho: &rho,
+            sigma: &sigma,
+            tau: &tau,
+            ..Default::default()
+        };
+        let outputs = func.evaluate(&inputs);
+        assert_eq!(outputs.zk, func.mgga_exc(&rho, &sigma, &[0.0, 0.0], &tau));
+    }
+}
@@ -0,0 +1,67 @@
+//! Bit flags reported by [Functional::flags](crate::functional::Functional::flags).
+//!
+//! The values are the `XC_FLAGS_*` definitions of the linked LibXC headers.
+
+/// The functional provides the energy density.
+pub const HAVE_EXC: i32 = libxc_sys::XC_FLAGS_HAVE_EXC as i32;
+/// The functional provides the first derivative.
+pub const HAVE_VXC: i32 = libxc_sys::XC_FLAGS_HAVE_VXC as i32;
+/// The functional provides the second derivative.
+pub const HAVE_FXC: i32 = libxc_sys::XC_FLAGS_HAVE_FXC as i32;
+/// The functional provides the third derivative.
+pub const HAVE_KXC: i32 = libxc_sys::XC_FLAGS_HAVE_KXC as i32;
+/// The functional provides the fourth derivative.
+pub const HAVE_LXC: i32 = libxc_sys::XC_FLAGS_HAVE_LXC as i32;
+/// The functional is defined in one dimension.
+pub const DIM_1D: i32 = libxc_sys::XC_FLAGS_1D as i32;
+/// The functional is defined in two dimensions.
+pub const DIM_2D: i32 = libxc_sys::XC_FLAGS_2D as i32;
+/// The functional is defined in three dimensions.
+pub const DIM_3D: i32 = libxc_sys::XC_FLAGS_3D as i32;
+/// The functional is range separated via the error function.
+pub const HYB_CAM: i32 = libxc_sys::XC_FLAGS_HYB_CAM as i32;
+/// The functional is range separated via the Yukawa function.
+pub const HYB_CAMY: i32 = libxc_sys::XC_FLAGS_HYB_CAMY as i32;
+/// The functional uses the VV10 non-local correlation kernel.
+pub const VV10: i32 = libxc_sys::XC_FLAGS_VV10 as i32;
+/// The functional is long-range corrected via the error function.
+pub const HYB_LC: i32 = libxc_sys::XC_FLAGS_HYB_LC as i32;
+/// The functional is long-range corrected via the Yukawa function.
+pub const HYB_LCY: i32 = libxc_sys::XC_FLAGS_HYB_LCY as i32;
+/// The functional is considered stable.
+pub const STABLE: i32 = libxc_sys::XC_FLAGS_STABLE as i32;
+/// The functional is under development and may have significant problems.
+pub const DEVELOPMENT: i32 = libxc_sys::XC_FLAGS_DEVELOPMENT as i32;
+/// The functional depends on the laplacian of the density.
+pub const NEEDS_LAPLACIAN: i32 = libxc_sys::XC_FLAGS_NEEDS_LAPLACIAN as i32;
+/// The functional depends on the kinetic energy density.
+pub const NEEDS_TAU: i32 = libxc_sys::XC_FLAGS_NEEDS_TAU as i32;
+
+/// Every flag paired with its name.
+pub const ALL: &[(i32, &str)] = &[
+    (HAVE_EXC, "HAVE_EXC"),
+    (HAVE_VXC, "HAVE_VXC"),
+    (HAVE_FXC, "HAVE_FXC"),
+    (HAVE_KXC, "HAVE_KXC"),
+    (HAVE_LXC, "HAVE_LXC"),
+    (DIM_1D, "1D"),
+    (DIM_2D, "2D"),
+    (DIM_3D, "3D"),
+    (HYB_CAM, "HYB_CAM"),
+    (HYB_CAMY, "HYB_CAMY"),
+    (VV10, "VV10"),
+    (HYB_LC, "HYB_LC"),
+    (HYB_LCY, "HYB_LCY"),
+    (STABLE, "STABLE"),
+    (DEVELOPMENT, "DEVELOPMENT"),
+    (NEEDS_LAPLACIAN, "NEEDS_LAPLACIAN"),
+    (NEEDS_TAU, "NEEDS_TAU"),
+];
+
+/// Returns the names of the flags set in `flags`, in increasing order of value.
+pub fn names(flags: i32) -> Vec<&'static str> {
+    ALL.iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
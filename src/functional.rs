@@ -1,18 +1,27 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
 use libxc_sys;
 
-use num_traits::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
 
 use crate::error::FunctionalError;
-use crate::util::{_rust_string_from_c_buf, functional_number};
+use crate::flags;
+use crate::util::{_rust_string_from_c_buf, _rust_string_from_c_buf_lossy, functional_number};
 
 /// Representation of a LibXC functional.
-#[derive(Clone)]
 pub struct Functional {
-    xc_func: *mut libxc_sys::xc_func_type,
-    xc_info: *const libxc_sys::xc_func_info_type,
+    pub(crate) xc_func: *mut libxc_sys::xc_func_type,
+    pub(crate) xc_info: *const libxc_sys::xc_func_info_type,
+    pub(crate) id: i32,
+    pub(crate) polarization: Polarization,
+    // LibXC offers no getter for the current external parameters.
+    pub(crate) ext_params: Vec<f64>,
+    pub(crate) dens_threshold: f64,
 }
 
-#[derive(Clone, Copy, Debug, Display, FromPrimitive)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, FromPrimitive)]
 pub enum FunctionalKind {
     Exchange = 0,
     Correlation = 1,
@@ -20,7 +29,7 @@ pub enum FunctionalKind {
     Kinetic = 3,
 }
 
-#[derive(Clone, Copy, Debug, Display, FromPrimitive)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash, FromPrimitive)]
 pub enum FunctionalFamily {
     Unknown = -1,
     LDA = 1,
@@ -33,29 +42,227 @@ pub enum FunctionalFamily {
     HybridLDA = 128,
 }
 
-#[derive(Clone, Copy, Debug, Display, ToPrimitive)]
+impl FunctionalFamily {
+    /// Decodes a LibXC family code, failing on any code which is not a single known family.
+    pub fn decode_strict(code: i32) -> Result<Self, FunctionalError> {
+        FunctionalFamily::from_i32(code).ok_or(FunctionalError::InvalidFamily(code))
+    }
+
+    /// Decodes a LibXC family code, keeping any code which is not a single known family, such as
+    /// a combination of families reported by a custom LibXC build.
+    pub fn decode_lenient(code: i32) -> FamilyCode {
+        match FunctionalFamily::from_i32(code) {
+            Some(family) => FamilyCode::Known(family),
+            None => FamilyCode::Other(code),
+        }
+    }
+}
+
+/// Family code reported by LibXC, decoded without discarding codes this crate does not know.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FamilyCode {
+    /// A single known family.
+    Known(FunctionalFamily),
+    /// Any other code, such as a combination of families.
+    Other(i32),
+}
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Polarization {
     Unpolarized = 1,
     Polarized = 2,
 }
 
+impl Polarization {
+    /// Returns the polarization with `n` spin channels, which must be 1 or 2.
+    pub fn from_nspin(n: u32) -> Result<Self, FunctionalError> {
+        match n {
+            1 => Ok(Polarization::Unpolarized),
+            2 => Ok(Polarization::Polarized),
+            _ => Err(FunctionalError::InvalidNspin(n)),
+        }
+    }
+}
+
+/// Spin channel of a polarized density.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum Spin {
+    Up,
+    Down,
+}
+
+/// Order of a derivative of the exchange-correlation energy.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum DerivativeOrder {
+    Exc = 0,
+    Vxc = 1,
+    Fxc = 2,
+    Kxc = 3,
+    Lxc = 4,
+}
+
+/// Exact exchange admixed by a functional.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HybridType {
+    /// No exact exchange.
+    None,
+    /// Fraction of exact exchange at every range.
+    Global(f64),
+    /// Coulomb-attenuated exact exchange split with an error function of range `omega`.
+    RangeSeparated { omega: f64, alpha: f64, beta: f64 },
+    /// Coulomb-attenuated exact exchange split with a Yukawa potential of range `omega`.
+    CAMY { omega: f64, alpha: f64, beta: f64 },
+}
+
+/// Bibliographic reference of a functional.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    /// Citation of the reference, such as `J. P. Perdew, ..., Phys. Rev. Lett. 77, 3865 (1996)`.
+    pub reference: String,
+    /// DOI of the reference, empty if LibXC does not record one.
+    pub doi: String,
+}
+
+/// Metadata of a LibXC functional stored as plain data.
+#[derive(Clone, Debug)]
+pub struct FunctionalInfo {
+    pub number: i32,
+    pub name: String,
+    pub kind: FunctionalKind,
+    pub family: FunctionalFamily,
+    pub flags: i32,
+}
+
+/// Number of components per point of each input array of a functional.
+///
+/// A count of zero means the array is not read by the functional.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputLayout {
+    pub rho: usize,
+    pub sigma: usize,
+    pub lapl: usize,
+    pub tau: usize,
+}
+
+/// Configuration of a functional stored as plain data.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionalSpec {
+    pub number: i32,
+    pub polarization: Polarization,
+    pub ext_params: Vec<f64>,
+}
+
+impl FunctionalSpec {
+    /// Constructs an evaluable [Functional] with the configuration of the spec.
+    ///
+    /// An empty list of external parameters keeps their defaults.
+    pub fn to_functional(&self) -> Result<Functional, FunctionalError> {
+        let mut func = Functional::from_id(self.number, self.polarization)?;
+        if !self.ext_params.is_empty() {
+            func.set_ext_params(&self.ext_params)?;
+        }
+        Ok(func)
+    }
+}
+
+impl FunctionalInfo {
+    /// Constructs an evaluable [Functional] from the metadata.
+    pub fn into_functional(
+        self,
+        polarization: Polarization,
+    ) -> Result<Functional, FunctionalError> {
+        Functional::from_id(self.number, polarization)
+    }
+}
+
 impl Functional {
     /// Constructs a [Functional] from a given id.
     pub fn from_id(id: i32, polarization: Polarization) -> Result<Self, FunctionalError> {
         // process `polarization` argument.
-        let polarization = polarization.to_i32().unwrap();
+        let nspin = polarization as i32;
         // Allocate a LibXC functional type.
         let xc_func: *mut libxc_sys::xc_func_type = unsafe { libxc_sys::xc_func_alloc() };
         // Initialize the LibXC functional type.
-        let init_result = unsafe { libxc_sys::xc_func_init(xc_func, id, polarization) };
+        let init_result = unsafe { libxc_sys::xc_func_init(xc_func, id, nspin) };
         if init_result != 0 {
+            unsafe { libxc_sys::xc_func_free(xc_func) };
+            // Tell a functional missing one polarization apart from an unknown functional.
+            let other = match polarization {
+                Polarization::Unpolarized => Polarization::Polarized,
+                Polarization::Polarized => Polarization::Unpolarized,
+            };
+            if Self::initializes(id, other) {
+                return Err(FunctionalError::UnsupportedPolarization(polarization));
+            }
             return Err(FunctionalError::FailedInitialization(init_result));
         }
         // Gather information about the functional.
         let xc_info: *const libxc_sys::xc_func_info_type =
             unsafe { libxc_sys::xc_func_get_info(xc_func) };
+        // LibXC initializes the external parameters to their defaults.
+        let n_ext_params = unsafe { libxc_sys::xc_func_info_get_n_ext_params(xc_info) };
+        let ext_params = (0..n_ext_params)
+            .map(|number| unsafe {
+                libxc_sys::xc_func_info_get_ext_params_default_value(xc_info, number)
+            })
+            .collect();
+        let dens_threshold = unsafe { (*xc_func).dens_threshold };
         // Return the initialized struct.
-        Ok(Functional { xc_func, xc_info })
+        Ok(Functional {
+            xc_func,
+            xc_info,
+            id,
+            polarization,
+            ext_params,
+            dens_threshold,
+        })
+    }
+
+    /// Constructs a [Functional] which takes ownership of a functional initialized through
+    /// `libxc_sys`.
+    ///
+    /// The external parameters are recorded as their defaults, so parameters set on `xc_func`
+    /// before the call are not reported by [Functional::ext_params].
+    ///
+    /// # Safety
+    ///
+    /// `xc_func` must have been allocated with `xc_func_alloc` and successfully initialized with
+    /// `xc_func_init` for `polarization`. The returned functional ends and frees `xc_func` when it
+    /// is dropped, so the caller must not end, free, or otherwise use it afterwards.
+    pub unsafe fn from_raw(
+        xc_func: *mut libxc_sys::xc_func_type,
+        polarization: Polarization,
+    ) -> Functional {
+        let xc_info = libxc_sys::xc_func_get_info(xc_func);
+        let id = libxc_sys::xc_func_info_get_number(xc_info);
+        let n_ext_params = libxc_sys::xc_func_info_get_n_ext_params(xc_info);
+        let ext_params = (0..n_ext_params)
+            .map(|number| libxc_sys::xc_func_info_get_ext_params_default_value(xc_info, number))
+            .collect();
+        let dens_threshold = (*xc_func).dens_threshold;
+        Functional {
+            xc_func,
+            xc_info,
+            id,
+            polarization,
+            ext_params,
+            dens_threshold,
+        }
+    }
+
+    // Returns true if LibXC can initialize the functional with the given polarization.
+    fn initializes(id: i32, polarization: Polarization) -> bool {
+        let xc_func: *mut libxc_sys::xc_func_type = unsafe { libxc_sys::xc_func_alloc() };
+        let init_result = unsafe { libxc_sys::xc_func_init(xc_func, id, polarization as i32) };
+        unsafe {
+            if init_result == 0 {
+                libxc_sys::xc_func_end(xc_func);
+            }
+            libxc_sys::xc_func_free(xc_func);
+        }
+        init_result == 0
     }
 
     /// Constructs a [Functional] from a given name.
@@ -69,40 +276,623 @@ impl Functional {
         }
     }
 
+    /// Constructs the unpolarized and polarized versions of the functional with a given id.
+    pub fn pair_for_both_spins(id: i32) -> Result<(Self, Self), FunctionalError> {
+        Ok((
+            Self::from_id(id, Polarization::Unpolarized)?,
+            Self::from_id(id, Polarization::Polarized)?,
+        ))
+    }
+
     /// Returns the name of the functional.
-    pub fn name(&self) -> String {
-        let c_buf = unsafe { libxc_sys::xc_func_info_get_name(self.xc_info) };
-        _rust_string_from_c_buf(c_buf)
+    pub fn name(&self) -> Result<String, FunctionalError> {
+        let c_buf = unsafe { libxc_sys::xc_func_info_get_name(self.info_ptr()) };
+        _rust_string_from_c_buf_lossy(c_buf)
     }
 
     /// Returns the ID of the functional.
     pub fn number(&self) -> i32 {
-        unsafe { libxc_sys::xc_func_info_get_number(self.xc_info) }
+        unsafe { libxc_sys::xc_func_info_get_number(self.info_ptr()) }
+    }
+
+    /// Returns true if the metadata of the functional describes the ID it was constructed from.
+    pub fn verify_number(&self) -> bool {
+        self.number() == self.id
     }
 
     /// Returns the kind of the functional.
-    pub fn kind(&self) -> FunctionalKind {
-        let result = unsafe { libxc_sys::xc_func_info_get_kind(self.xc_info) };
-        FunctionalKind::from_i32(result).unwrap()
+    pub fn kind(&self) -> Result<FunctionalKind, FunctionalError> {
+        let result = unsafe { libxc_sys::xc_func_info_get_kind(self.info_ptr()) };
+        FunctionalKind::from_i32(result).ok_or(FunctionalError::InvalidKind(result))
+    }
+
+    /// Returns the kind of the functional, failing if LibXC reports a code this crate does not
+    /// know.
+    ///
+    /// This is equivalent to [Functional::kind].
+    pub fn try_kind(&self) -> Result<FunctionalKind, FunctionalError> {
+        self.kind()
+    }
+
+    /// Returns true if the functional is an exchange functional.
+    pub fn is_exchange(&self) -> bool {
+        matches!(self.kind(), Ok(FunctionalKind::Exchange))
+    }
+
+    /// Returns true if the functional is a correlation functional.
+    pub fn is_correlation(&self) -> bool {
+        matches!(self.kind(), Ok(FunctionalKind::Correlation))
+    }
+
+    /// Returns true if the functional is a combined exchange-correlation functional.
+    pub fn is_exchange_correlation(&self) -> bool {
+        matches!(self.kind(), Ok(FunctionalKind::ExchangeCorrelation))
+    }
+
+    /// Returns true if the functional provides both exchange and correlation, so that it is a
+    /// valid choice without a partner functional.
+    pub fn is_complete_xc(&self) -> bool {
+        self.is_exchange_correlation()
+    }
+
+    /// Returns true if the functional is a kinetic energy functional.
+    pub fn is_kinetic(&self) -> bool {
+        matches!(self.kind(), Ok(FunctionalKind::Kinetic))
+    }
+
+    /// Returns the conventional sign of the energy of the functional.
+    ///
+    /// Exchange and correlation energies are negative and kinetic energies are positive. Returns
+    /// 0 if the kind of the functional is unknown.
+    pub fn expected_sign(&self) -> i8 {
+        match self.kind() {
+            Ok(FunctionalKind::Exchange)
+            | Ok(FunctionalKind::Correlation)
+            | Ok(FunctionalKind::ExchangeCorrelation) => -1,
+            Ok(FunctionalKind::Kinetic) => 1,
+            Err(_) => 0,
+        }
     }
 
     /// Returns the family of the functional.
-    pub fn family(&self) -> FunctionalFamily {
-        let result = unsafe { libxc_sys::xc_func_info_get_family(self.xc_info) };
-        FunctionalFamily::from_i32(result).unwrap()
+    pub fn family(&self) -> Result<FunctionalFamily, FunctionalError> {
+        let result = unsafe { libxc_sys::xc_func_info_get_family(self.info_ptr()) };
+        FunctionalFamily::decode_strict(result)
+    }
+
+    /// Returns the family of the functional, failing if LibXC reports a code this crate does not
+    /// know.
+    ///
+    /// This is equivalent to [Functional::family].
+    pub fn try_family(&self) -> Result<FunctionalFamily, FunctionalError> {
+        self.family()
+    }
+
+    /// Returns the family of the functional, failing on any family code this crate does not
+    /// know.
+    ///
+    /// This is equivalent to [Functional::family].
+    pub fn try_family_strict(&self) -> Result<FunctionalFamily, FunctionalError> {
+        self.family()
+    }
+
+    /// Returns the family code of the functional, keeping codes this crate does not know.
+    pub fn family_lenient(&self) -> FamilyCode {
+        let result = unsafe { libxc_sys::xc_func_info_get_family(self.info_ptr()) };
+        FunctionalFamily::decode_lenient(result)
     }
 
     // I do not understand why this is not an array of integers.
     // TODO: Map this result to an enum for clarity.
     /// Returns the flags of the functional.
     pub fn flags(&self) -> i32 {
-        unsafe { libxc_sys::xc_func_info_get_flags(self.xc_info) }
+        unsafe { libxc_sys::xc_func_info_get_flags(self.info_ptr()) }
+    }
+
+    /// Returns the metadata of the functional as plain data.
+    pub fn info(&self) -> Result<FunctionalInfo, FunctionalError> {
+        Ok(FunctionalInfo {
+            number: self.number(),
+            name: self.name()?,
+            kind: self.kind()?,
+            family: self.family()?,
+            flags: self.flags(),
+        })
+    }
+
+    /// Returns every reference of the functional.
+    pub fn references(&self) -> Vec<Reference> {
+        (0..libxc_sys::XC_MAX_REFERENCES as i32)
+            .map(|number| unsafe {
+                libxc_sys::xc_func_info_get_references(self.info_ptr(), number)
+            })
+            .take_while(|reference| !reference.is_null())
+            .filter_map(|reference| {
+                let ref_buf = unsafe { libxc_sys::xc_func_reference_get_ref(reference) };
+                let doi_buf = unsafe { libxc_sys::xc_func_reference_get_doi(reference) };
+                Some(Reference {
+                    reference: _rust_string_from_c_buf_lossy(ref_buf).ok()?,
+                    doi: _rust_string_from_c_buf(doi_buf).unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the DOI of every reference of the functional.
+    pub fn all_dois(&self) -> Vec<String> {
+        self.references()
+            .into_iter()
+            .map(|reference| reference.doi)
+            .filter(|doi| !doi.is_empty())
+            .collect()
+    }
+
+    /// Returns the primary reference of the functional formatted as a single citation line.
+    pub fn citation_text(&self) -> String {
+        match self.references().into_iter().next() {
+            Some(reference) if reference.doi.is_empty() => reference.reference,
+            Some(reference) => format!("{}, doi:{}", reference.reference, reference.doi),
+            None => String::new(),
+        }
+    }
+
+    /// Returns a multi-line human-readable summary of the metadata of the functional.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let name = self.name().unwrap_or_else(|_| "unknown".to_owned());
+        let kind = match self.kind() {
+            Ok(kind) => kind.to_string(),
+            Err(_) => "unknown".to_owned(),
+        };
+        // Writing to a String cannot fail.
+        writeln!(report, "Name: {}", name).unwrap();
+        writeln!(report, "ID: {}", self.number()).unwrap();
+        writeln!(report, "Kind: {}", kind).unwrap();
+        writeln!(report, "Family: {}", self.family_str()).unwrap();
+        writeln!(report, "Flags: {}", flags::names(self.flags()).join(" | ")).unwrap();
+        writeln!(report, "External parameters:").unwrap();
+        for (index, value) in self.ext_params.iter().enumerate() {
+            let index = index as i32;
+            let name = self.ext_param_name(index).unwrap_or_default();
+            let default = self.ext_param_default(index).unwrap_or(f64::NAN);
+            writeln!(report, "  {} = {} (default {})", name, value, default).unwrap();
+        }
+        writeln!(report, "Reference: {}", self.citation_text()).unwrap();
+        report
+    }
+
+    /// Returns true if the functional is marked as stable.
+    pub fn is_stable(&self) -> bool {
+        self.has_flag(flags::STABLE)
+    }
+
+    /// Returns true if the functional is marked as under development.
+    pub fn is_development(&self) -> bool {
+        self.has_flag(flags::DEVELOPMENT)
+    }
+
+    /// Returns the family of the functional in the form used as a prefix of LibXC names.
+    pub fn family_str(&self) -> &'static str {
+        match self.family() {
+            Ok(FunctionalFamily::LDA) => "LDA",
+            Ok(FunctionalFamily::GGA) => "GGA",
+            Ok(FunctionalFamily::MGGA) => "MGGA",
+            Ok(FunctionalFamily::LCA) => "LCA",
+            Ok(FunctionalFamily::OEP) => "OEP",
+            Ok(FunctionalFamily::HybridGGA) => "HYB_GGA",
+            Ok(FunctionalFamily::HybridMGGA) => "HYB_MGGA",
+            Ok(FunctionalFamily::HybridLDA) => "HYB_LDA",
+            Ok(FunctionalFamily::Unknown) | Err(_) => "UNKNOWN",
+        }
+    }
+
+    /// Returns true if the functional can be evaluated alongside `other` on the same inputs.
+    ///
+    /// Compatible functionals share a polarization, are evaluated through the same LDA, GGA, or
+    /// MGGA interface, and are defined in the same dimensions.
+    pub fn is_compatible_with(&self, other: &Functional) -> bool {
+        let dimensions = flags::DIM_1D | flags::DIM_2D | flags::DIM_3D;
+        self.polarization == other.polarization
+            && self.base_family().is_some()
+            && self.base_family() == other.base_family()
+            && self.flags() & dimensions == other.flags() & dimensions
+    }
+
+    // Returns the family whose evaluators apply to the functional, ignoring hybridization.
+    pub(crate) fn base_family(&self) -> Option<FunctionalFamily> {
+        if self.info_ptr().is_null() {
+            return None;
+        }
+        match self.family() {
+            Ok(FunctionalFamily::LDA) | Ok(FunctionalFamily::HybridLDA) => {
+                Some(FunctionalFamily::LDA)
+            }
+            Ok(FunctionalFamily::GGA) | Ok(FunctionalFamily::HybridGGA) => {
+                Some(FunctionalFamily::GGA)
+            }
+            Ok(FunctionalFamily::MGGA) | Ok(FunctionalFamily::HybridMGGA) => {
+                Some(FunctionalFamily::MGGA)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if LibXC provides the potential of the functional analytically.
+    ///
+    /// Functionals without an analytic potential must be differentiated numerically.
+    pub fn has_analytic_potential(&self) -> bool {
+        self.has_flag(flags::HAVE_VXC)
+    }
+
+    /// Returns true if the functional is defined in `dim` spatial dimensions.
+    pub fn supports_dimension(&self, dim: u8) -> bool {
+        match dim {
+            1 => self.has_flag(flags::DIM_1D),
+            2 => self.has_flag(flags::DIM_2D),
+            3 => self.has_flag(flags::DIM_3D),
+            _ => false,
+        }
+    }
+
+    /// Returns the highest order of derivative of the energy provided by the functional, from 0
+    /// for the energy alone to 4 for the fourth derivative.
+    pub fn max_derivative_order(&self) -> u8 {
+        let orders = [
+            flags::HAVE_EXC,
+            flags::HAVE_VXC,
+            flags::HAVE_FXC,
+            flags::HAVE_KXC,
+            flags::HAVE_LXC,
+        ];
+        // LibXC provides every order up to the highest one.
+        orders
+            .iter()
+            .take_while(|flag| self.has_flag(**flag))
+            .count()
+            .saturating_sub(1) as u8
+    }
+
+    /// Returns true if the functional depends on the laplacian of the density.
+    pub fn needs_laplacian(&self) -> bool {
+        self.has_flag(flags::NEEDS_LAPLACIAN)
+    }
+
+    /// Returns true if the functional depends on the kinetic energy density.
+    ///
+    /// Deorbitalized meta-GGAs replace the kinetic energy density with the laplacian.
+    pub fn needs_tau(&self) -> bool {
+        self.base_family() == Some(FunctionalFamily::MGGA) && self.has_flag(flags::NEEDS_TAU)
+    }
+
+    /// Returns the unit of the energies produced by the functional.
+    pub fn energy_units(&self) -> &'static str {
+        "Hartree"
+    }
+
+    /// Returns the polarization of the functional.
+    pub fn polarization(&self) -> Polarization {
+        self.polarization
+    }
+
+    /// Returns the exact exchange admixed by the functional.
+    pub fn hybrid_type(&self) -> HybridType {
+        match self.family() {
+            Ok(FunctionalFamily::HybridLDA)
+            | Ok(FunctionalFamily::HybridGGA)
+            | Ok(FunctionalFamily::HybridMGGA) => (),
+            _ => return HybridType::None,
+        }
+        let (mut omega, mut alpha, mut beta) = (0.0, 0.0, 0.0);
+        if self.has_flag(flags::HYB_CAM | flags::HYB_CAMY | flags::HYB_LC | flags::HYB_LCY) {
+            unsafe { libxc_sys::xc_hyb_cam_coef(self.xc_func, &mut omega, &mut alpha, &mut beta) };
+        }
+        if self.has_flag(flags::HYB_CAM | flags::HYB_LC) {
+            HybridType::RangeSeparated { omega, alpha, beta }
+        } else if self.has_flag(flags::HYB_CAMY | flags::HYB_LCY) {
+            HybridType::CAMY { omega, alpha, beta }
+        } else {
+            HybridType::Global(unsafe { libxc_sys::xc_hyb_exx_coef(self.xc_func) })
+        }
+    }
+
+    /// Returns true if the functional uses the VV10 non-local correlation kernel.
+    pub fn uses_vv10(&self) -> bool {
+        self.has_flag(flags::VV10)
+    }
+
+    /// Returns the `b` and `C` coefficients of the VV10 non-local correlation kernel.
+    pub fn nlc_coef(&self) -> (f64, f64) {
+        let mut b: f64 = 0.0;
+        let mut c: f64 = 0.0;
+        unsafe { libxc_sys::xc_nlc_coef(self.xc_func, &mut b, &mut c) };
+        (b, c)
+    }
+
+    /// Overrides the `b` and `C` coefficients of the VV10 non-local correlation kernel.
+    pub fn set_nlc_coef(&mut self, b: f64, c: f64) {
+        // LibXC reads the coefficients directly from the functional type.
+        unsafe {
+            (*self.xc_func).nlc_b = b;
+            (*self.xc_func).nlc_C = c;
+        }
+    }
+
+    /// Returns the configuration of the functional as plain data.
+    pub fn spec(&self) -> FunctionalSpec {
+        FunctionalSpec {
+            number: self.number(),
+            polarization: self.polarization,
+            ext_params: self.ext_params.clone(),
+        }
+    }
+
+    /// Returns the number of external parameters of the functional.
+    pub fn n_ext_params(&self) -> usize {
+        self.ext_params.len()
+    }
+
+    /// Returns the current values of the external parameters of the functional.
+    pub fn ext_params(&self) -> &[f64] {
+        &self.ext_params
+    }
+
+    /// Returns the name of the external parameter at `index`.
+    pub fn ext_param_name(&self, index: i32) -> Result<String, FunctionalError> {
+        self.check_ext_param_index(index)?;
+        let c_buf = unsafe { libxc_sys::xc_func_info_get_ext_params_name(self.info_ptr(), index) };
+        _rust_string_from_c_buf(c_buf)
+    }
+
+    /// Returns the default value of the external parameter at `index`.
+    pub fn ext_param_default(&self, index: i32) -> Result<f64, FunctionalError> {
+        self.check_ext_param_index(index)?;
+        Ok(unsafe { libxc_sys::xc_func_info_get_ext_params_default_value(self.info_ptr(), index) })
+    }
+
+    /// Returns the current value of each external parameter keyed by name.
+    pub fn ext_params_map(&self) -> HashMap<String, f64> {
+        self.ext_params
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                self.ext_param_name(index as i32)
+                    .ok()
+                    .map(|name| (name, *value))
+            })
+            .collect()
+    }
+
+    /// Returns the index of the external parameter called `name`.
+    pub fn ext_param_index(&self, name: &str) -> Option<i32> {
+        (0..self.n_ext_params() as i32)
+            .find(|index| matches!(self.ext_param_name(*index), Ok(ref found) if found == name))
+    }
+
+    /// Overrides the values of all external parameters of the functional, failing with the
+    /// number of values given unless there is one value per external parameter.
+    pub fn set_ext_params(&mut self, ext_params: &[f64]) -> Result<(), FunctionalError> {
+        if ext_params.len() != self.n_ext_params() {
+            return Err(FunctionalError::InvalidExtParam(ext_params.len() as i32));
+        }
+        unsafe { libxc_sys::xc_func_set_ext_params(self.xc_func, ext_params.as_ptr()) };
+        self.ext_params = ext_params.to_vec();
+        Ok(())
+    }
+
+    /// Multiplies the current value of the external parameter at `index` by `factor`.
+    pub fn scale_ext_param(&mut self, index: i32, factor: f64) -> Result<(), FunctionalError> {
+        self.check_ext_param_index(index)?;
+        let mut ext_params = self.ext_params.clone();
+        ext_params[index as usize] *= factor;
+        self.set_ext_params(&ext_params)
+    }
+
+    /// Fetches the metadata of the functional again from its LibXC allocation.
+    pub fn refresh_info(&mut self) {
+        self.xc_info = unsafe { libxc_sys::xc_func_get_info(self.xc_func) };
+    }
+
+    /// Returns the density below which LibXC treats a point as empty.
+    pub fn dens_threshold(&self) -> f64 {
+        self.dens_threshold
+    }
+
+    /// Overrides the density below which LibXC treats a point as empty.
+    pub fn set_dens_threshold(&mut self, threshold: f64) {
+        unsafe { libxc_sys::xc_func_set_dens_threshold(self.xc_func, threshold) };
+        self.dens_threshold = threshold;
+    }
+
+    /// Clears the cached metadata pointer of the functional.
+    ///
+    /// The metadata is owned by LibXC and freed with the functional, so nothing is released here.
+    /// Metadata accessors fetch the pointer again from the LibXC allocation until
+    /// [Functional::refresh_info] caches it again.
+    pub fn drop_info(&mut self) {
+        self.xc_info = std::ptr::null();
+    }
+
+    // Returns the metadata pointer, fetching it from the LibXC allocation if it was cleared.
+    fn info_ptr(&self) -> *const libxc_sys::xc_func_info_type {
+        if self.xc_info.is_null() && !self.xc_func.is_null() {
+            unsafe { libxc_sys::xc_func_get_info(self.xc_func) }
+        } else {
+            self.xc_info
+        }
+    }
+
+    /// Returns the number of spin channels expected per point, which is 1 or 2.
+    pub fn n_spin(&self) -> usize {
+        match self.polarization {
+            Polarization::Unpolarized => 1,
+            Polarization::Polarized => 2,
+        }
+    }
+
+    /// Returns the length of the buffer holding a derivative of the energy with respect to the
+    /// density alone (`zk`, `vrho`, `v2rho2`, ...) over `npoints` points.
+    pub fn output_len(&self, npoints: usize, order: DerivativeOrder) -> usize {
+        // Polarized derivatives of order n pack n + 1 unique spin combinations per point.
+        match self.polarization {
+            Polarization::Unpolarized => npoints,
+            Polarization::Polarized => npoints * (order as usize + 1),
+        }
+    }
+
+    /// Returns the number of components per point of each input array of the functional.
+    pub fn input_layout(&self) -> InputLayout {
+        let nspin = self.n_spin();
+        let (sigma, mgga) = match self.base_family() {
+            Some(FunctionalFamily::LDA) => (false, false),
+            Some(FunctionalFamily::GGA) => (true, false),
+            Some(FunctionalFamily::MGGA) => (true, true),
+            _ => {
+                return InputLayout {
+                    rho: 0,
+                    sigma: 0,
+                    lapl: 0,
+                    tau: 0,
+                }
+            }
+        };
+        let count = |required: bool, components: usize| if required { components } else { 0 };
+        InputLayout {
+            rho: nspin,
+            sigma: count(sigma, 2 * nspin - 1),
+            lapl: count(mgga && self.needs_laplacian(), nspin),
+            tau: count(mgga && self.needs_tau(), nspin),
+        }
+    }
+
+    /// Returns the number of components per point of `vrho`.
+    pub fn vxc_components(&self) -> usize {
+        self.output_len(1, DerivativeOrder::Vxc)
+    }
+
+    /// Returns the number of components per point of `vsigma`, which is 0 for functionals that do
+    /// not read the gradient.
+    pub fn vsigma_components(&self) -> usize {
+        // Each first derivative shares the layout of the input it is taken with respect to.
+        self.input_layout().sigma
+    }
+
+    /// Returns the number of components per point of `vlapl`.
+    pub fn vlapl_components(&self) -> usize {
+        self.input_layout().lapl
+    }
+
+    /// Returns the number of components per point of `vtau`.
+    pub fn vtau_components(&self) -> usize {
+        self.input_layout().tau
+    }
+
+    /// Returns the number of components per point of `v2rho2`.
+    pub fn fxc_components(&self) -> usize {
+        self.output_len(1, DerivativeOrder::Fxc)
+    }
+
+    /// Returns the number of components per point of `v3rho3`.
+    pub fn kxc_components(&self) -> usize {
+        self.output_len(1, DerivativeOrder::Kxc)
+    }
+
+    // Checks that the functional can provide the derivative `flag` through the evaluators of
+    // `family` without handing LibXC a null pointer.
+    pub(crate) fn check_evaluator(
+        &self,
+        family: FunctionalFamily,
+        flag: i32,
+    ) -> Result<(), FunctionalError> {
+        if self.xc_func.is_null() || self.info_ptr().is_null() {
+            return Err(FunctionalError::Uninitialized);
+        }
+        if self.base_family() != Some(family) {
+            return Err(FunctionalError::UnsupportedFamily(family));
+        }
+        if !self.has_flag(flag) {
+            return Err(FunctionalError::UnsupportedDerivative);
+        }
+        Ok(())
+    }
+
+    // Checks that `index` identifies an external parameter before it is handed to LibXC, which
+    // does not validate it.
+    fn check_ext_param_index(&self, index: i32) -> Result<(), FunctionalError> {
+        if index < 0 || index as usize >= self.n_ext_params() {
+            return Err(FunctionalError::InvalidExtParam(index));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn has_flag(&self, flag: i32) -> bool {
+        self.flags() & flag != 0
+    }
+}
+
+impl Clone for Functional {
+    fn clone(&self) -> Self {
+        // The clone gets its own LibXC allocation so that neither copy frees the other.
+        let mut func = Functional::from_id(self.number(), self.polarization)
+            .expect("an initialized functional can be initialized again");
+        if !self.ext_params.is_empty() {
+            func.set_ext_params(&self.ext_params)
+                .expect("the clone has the external parameters of the original");
+        }
+        func.set_dens_threshold(self.dens_threshold);
+        let (b, c) = self.nlc_coef();
+        func.set_nlc_coef(b, c);
+        func
+    }
+}
+
+// Functionals are equal if they share a spec. External parameters are compared bitwise so that
+// equality stays reflexive and consistent with hashing.
+impl PartialEq for Functional {
+    fn eq(&self, other: &Self) -> bool {
+        self.number() == other.number()
+            && self.polarization == other.polarization
+            && self.ext_params.len() == other.ext_params.len()
+            && self
+                .ext_params
+                .iter()
+                .zip(other.ext_params.iter())
+                .all(|(a, b)| a.to_bits() == b.to_bits())
+    }
+}
+
+impl Eq for Functional {}
+
+impl Hash for Functional {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.number().hash(state);
+        self.polarization.hash(state);
+        for param in &self.ext_params {
+            param.to_bits().hash(state);
+        }
+    }
+}
+
+impl Drop for Functional {
+    fn drop(&mut self) {
+        if !self.xc_func.is_null() {
+            unsafe {
+                libxc_sys::xc_func_end(self.xc_func);
+                libxc_sys::xc_func_free(self.xc_func);
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::functional::{Functional, FunctionalFamily, FunctionalKind, Polarization};
+    use num_traits::FromPrimitive;
+
+    use crate::error::FunctionalError;
+    use crate::functional::{
+        DerivativeOrder, FamilyCode, Functional, FunctionalFamily, FunctionalKind, HybridType,
+        InputLayout, Polarization,
+    };
+    use crate::ids;
 
     #[test]
     fn from_id() {
@@ -113,6 +903,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pair_for_both_spins() {
+        let (unpolarized, polarized) = Functional::pair_for_both_spins(ids::GGA_X_PBE).unwrap();
+        assert_eq!(unpolarized.number(), polarized.number());
+        assert_eq!(unpolarized.polarization(), Polarization::Unpolarized);
+        assert_eq!(polarized.polarization(), Polarization::Polarized);
+        assert!(Functional::pair_for_both_spins(-1).is_err());
+    }
+
+    #[test]
+    fn from_id_unknown() {
+        // Every functional of the standard LibXC build supports both polarizations, so only the
+        // unknown id path can be exercised here.
+        match Functional::from_id(-1, Polarization::Polarized) {
+            Err(FunctionalError::FailedInitialization(_)) => (),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn from_name() {
         let func = Functional::from_name("XC_GGA_X_GAM", Polarization::Polarized);
@@ -122,10 +931,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_name_interior_nul() {
+        let func = Functional::from_name("XC_GGA\0_X_GAM", Polarization::Polarized);
+        assert!(func.is_err());
+    }
+
     #[test]
     fn name() {
         let func = Functional::from_id(1, Polarization::Polarized).unwrap();
-        let name = func.name();
+        let name = func.name().unwrap();
         assert_eq!(name, "Slater exchange");
     }
 
@@ -136,34 +951,540 @@ mod tests {
         assert_eq!(number, func.number());
     }
 
+    #[test]
+    fn verify_number() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap();
+        assert!(func.verify_number());
+        assert!(func.clone().verify_number());
+    }
+
     #[test]
     fn kind() {
         let func = Functional::from_id(1, Polarization::Polarized).unwrap();
         match func.kind() {
-            FunctionalKind::Exchange => (),
+            Ok(FunctionalKind::Exchange) => (),
             _ => panic!(),
         }
     }
 
+    #[test]
+    fn kind_and_family_standard() {
+        for id in ids::ALL {
+            let func = Functional::from_id(*id, Polarization::Unpolarized).unwrap();
+            assert!(func.kind().is_ok());
+            assert!(func.family().is_ok());
+            assert_eq!(func.try_kind().unwrap(), func.kind().unwrap());
+            assert_eq!(func.try_family().unwrap(), func.family().unwrap());
+        }
+    }
+
+    #[test]
+    fn is_exchange() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert!(func.is_exchange());
+        assert!(!func.is_correlation());
+    }
+
+    #[test]
+    fn is_correlation() {
+        let func = Functional::from_id(12, Polarization::Unpolarized).unwrap();
+        assert!(func.is_correlation());
+        assert!(!func.is_exchange());
+        assert!(!func.is_exchange_correlation());
+        assert!(!func.is_kinetic());
+    }
+
+    #[test]
+    fn expected_sign() {
+        let func = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.expected_sign(), -1);
+        assert!(func.lda_exc(&[0.2])[0] < 0.0);
+        let func = Functional::from_id(ids::LDA_K_TF, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.expected_sign(), 1);
+    }
+
+    #[test]
+    fn is_complete_xc() {
+        let b3lyp = Functional::from_id(ids::HYB_GGA_XC_B3LYP, Polarization::Unpolarized).unwrap();
+        let pbe_x = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(b3lyp.is_complete_xc());
+        assert!(!pbe_x.is_complete_xc());
+    }
+
     #[test]
     fn family() {
         let func = Functional::from_id(32, Polarization::Polarized).unwrap();
         match func.family() {
-            FunctionalFamily::GGA => (),
+            Ok(FunctionalFamily::GGA) => (),
             _ => panic!(),
         }
     }
 
+    #[test]
+    fn decode_family() {
+        let combined = FunctionalFamily::LDA as i32 | FunctionalFamily::GGA as i32;
+        assert!(FunctionalFamily::decode_strict(combined).is_err());
+        assert_eq!(
+            FunctionalFamily::decode_lenient(combined),
+            FamilyCode::Other(combined)
+        );
+        assert_eq!(
+            FunctionalFamily::decode_lenient(2),
+            FamilyCode::Known(FunctionalFamily::GGA)
+        );
+        assert_eq!(
+            FunctionalFamily::decode_strict(2).unwrap(),
+            FunctionalFamily::GGA
+        );
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert_eq!(
+            func.family_lenient(),
+            FamilyCode::Known(func.family().unwrap())
+        );
+        assert_eq!(func.try_family_strict().unwrap(), func.family().unwrap());
+    }
+
     #[test]
     fn flags() {
         let func = Functional::from_id(1, Polarization::Polarized).unwrap();
         assert_eq!(func.flags(), 135);
     }
 
+    #[test]
+    fn polarization_from_nspin() {
+        assert_eq!(
+            Polarization::from_nspin(1).unwrap(),
+            Polarization::Unpolarized
+        );
+        assert_eq!(
+            Polarization::from_nspin(2).unwrap(),
+            Polarization::Polarized
+        );
+        assert!(Polarization::from_nspin(3).is_err());
+    }
+
+    #[test]
+    fn polarization_from_i32() {
+        assert_eq!(Polarization::from_i32(2), Some(Polarization::Polarized));
+        assert_eq!(Polarization::from_i32(1), Some(Polarization::Unpolarized));
+        assert_eq!(Polarization::from_i32(3), None);
+    }
+
+    #[test]
+    fn max_derivative_order() {
+        // The flags of Slater exchange, 135, provide the energy up to the second derivative.
+        let func = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.flags(), 135);
+        assert_eq!(func.max_derivative_order(), 2);
+    }
+
+    #[test]
+    fn polarization() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.polarization(), Polarization::Unpolarized);
+    }
+
+    #[test]
+    fn family_str() {
+        let func = Functional::from_id(402, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.family_str(), "HYB_GGA");
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.family_str(), "LDA");
+    }
+
+    #[test]
+    fn is_compatible_with() {
+        let pbe_x = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let pbe_c = Functional::from_id(130, Polarization::Unpolarized).unwrap();
+        let tpss_x = Functional::from_id(202, Polarization::Unpolarized).unwrap();
+        let pbe_c_pol = Functional::from_id(130, Polarization::Polarized).unwrap();
+        assert!(pbe_x.is_compatible_with(&pbe_c));
+        assert!(!pbe_x.is_compatible_with(&tpss_x));
+        assert!(!pbe_x.is_compatible_with(&pbe_c_pol));
+    }
+
+    #[test]
+    fn has_analytic_potential() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(func.has_analytic_potential());
+    }
+
+    #[test]
+    fn supports_dimension() {
+        let func = Functional::from_id(ids::LDA_X_2D, Polarization::Unpolarized).unwrap();
+        assert!(func.supports_dimension(2));
+        assert!(!func.supports_dimension(3));
+        let func = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        assert!(func.supports_dimension(3));
+        assert!(!func.supports_dimension(4));
+    }
+
+    #[test]
+    fn needs_laplacian() {
+        let scanl = Functional::from_id(ids::MGGA_X_SCANL, Polarization::Unpolarized).unwrap();
+        let pbe = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(scanl.needs_laplacian());
+        assert!(!pbe.needs_laplacian());
+    }
+
+    #[test]
+    fn needs_tau() {
+        let scan = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let scanl = Functional::from_id(ids::MGGA_X_SCANL, Polarization::Unpolarized).unwrap();
+        assert!(scan.needs_tau());
+        assert!(!scanl.needs_tau());
+    }
+
+    #[test]
+    fn hybrid_type() {
+        let b3lyp = Functional::from_id(ids::HYB_GGA_XC_B3LYP, Polarization::Unpolarized).unwrap();
+        match b3lyp.hybrid_type() {
+            HybridType::Global(exx) => assert!((exx - 0.2).abs() < 1e-12),
+            _ => panic!(),
+        }
+        let cam =
+            Functional::from_id(ids::HYB_GGA_XC_CAM_B3LYP, Polarization::Unpolarized).unwrap();
+        match cam.hybrid_type() {
+            HybridType::RangeSeparated { omega, alpha, beta } => {
+                assert!((omega - 0.33).abs() < 1e-12);
+                assert!((alpha - 0.65).abs() < 1e-12);
+                assert!((beta - -0.46).abs() < 1e-12);
+            }
+            _ => panic!(),
+        }
+        let pbe = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert_eq!(pbe.hybrid_type(), HybridType::None);
+    }
+
+    #[test]
+    fn uses_vv10() {
+        let vv10 = Functional::from_id(ids::GGA_XC_VV10, Polarization::Unpolarized).unwrap();
+        let pbe = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(vv10.uses_vv10());
+        assert!(!pbe.uses_vv10());
+    }
+
+    #[test]
+    fn set_nlc_coef() {
+        let mut func = Functional::from_id(255, Polarization::Unpolarized).unwrap();
+        func.set_nlc_coef(6.3, 0.0105);
+        assert_eq!(func.nlc_coef(), (6.3, 0.0105));
+    }
+
+    #[test]
+    fn set_ext_params() {
+        let mut func =
+            Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let mut params = func.ext_params().to_vec();
+        assert_eq!(params.len(), func.n_ext_params());
+        params[0] *= 0.5;
+        func.set_ext_params(&params).unwrap();
+        assert_eq!(func.ext_params(), &params[..]);
+        assert_eq!(func.clone().ext_params(), &params[..]);
+        match func.set_ext_params(&params[1..]) {
+            Err(FunctionalError::InvalidExtParam(n)) => assert_eq!(n as usize, params.len() - 1),
+            _ => panic!(),
+        }
+        assert_eq!(func.ext_params(), &params[..]);
+    }
+
+    #[test]
+    fn scale_ext_param() {
+        let mut func =
+            Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let value = func.ext_params()[1];
+        func.scale_ext_param(1, 2.0).unwrap();
+        assert_eq!(func.ext_params()[1], 2.0 * value);
+        assert_eq!(func.ext_params()[0], func.ext_param_default(0).unwrap());
+        let params = func.ext_params().to_vec();
+        let n = func.n_ext_params() as i32;
+        match func.scale_ext_param(n, 2.0) {
+            Err(FunctionalError::InvalidExtParam(index)) => assert_eq!(index, n),
+            _ => panic!(),
+        }
+        assert!(func.scale_ext_param(-1, 2.0).is_err());
+        assert_eq!(func.ext_params(), &params[..]);
+    }
+
+    #[test]
+    fn ext_param_index() {
+        let func = Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let index = func.ext_param_index("_omega_HF").unwrap();
+        assert!((index as usize) < func.n_ext_params());
+        assert_eq!(func.ext_param_name(index).unwrap(), "_omega_HF");
+        assert_eq!(func.ext_param_index("_not_a_parameter"), None);
+    }
+
+    #[test]
+    fn ext_param_name_out_of_range() {
+        let func = Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let n = func.n_ext_params() as i32;
+        match func.ext_param_name(n) {
+            Err(FunctionalError::InvalidExtParam(index)) => assert_eq!(index, n),
+            _ => panic!(),
+        }
+        assert!(func.ext_param_name(-1).is_err());
+    }
+
+    #[test]
+    fn ext_params_map() {
+        let func = Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let map = func.ext_params_map();
+        assert_eq!(map.len(), func.n_ext_params());
+        let index = func.ext_param_index("_omega_HF").unwrap();
+        assert_eq!(map["_omega_HF"], func.ext_param_default(index).unwrap());
+    }
+
+    #[test]
+    fn ext_param_default_out_of_range() {
+        let func = Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let n = func.n_ext_params() as i32;
+        assert!(func.ext_param_default(n - 1).is_ok());
+        match func.ext_param_default(n) {
+            Err(FunctionalError::InvalidExtParam(index)) => assert_eq!(index, n),
+            _ => panic!(),
+        }
+        assert!(func.ext_param_default(-1).is_err());
+    }
+
+    #[test]
+    fn dens_threshold() {
+        let mut func = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        assert!(func.dens_threshold() > 0.0);
+        func.set_dens_threshold(1e-8);
+        assert_eq!(func.dens_threshold(), 1e-8);
+        assert_eq!(func.clone().dens_threshold(), 1e-8);
+    }
+
+    #[test]
+    fn drop_info() {
+        let mut func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        let name = func.name().unwrap();
+        func.drop_info();
+        assert_eq!(func.name().unwrap(), name);
+        assert_eq!(func.number(), ids::GGA_X_PBE);
+        assert_eq!(func.family().unwrap(), FunctionalFamily::GGA);
+        assert!(!func.gga_exc(&[0.1], &[0.01]).is_empty());
+        func.refresh_info();
+        assert!(func.verify_number());
+    }
+
+    #[test]
+    fn spec() {
+        let mut func =
+            Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let mut params = func.ext_params().to_vec();
+        params[0] *= 0.5;
+        func.set_ext_params(&params).unwrap();
+        let spec = func.spec();
+        assert_eq!(spec.number, ids::HYB_GGA_XC_HSE06);
+        assert_eq!(spec.ext_params, params);
+        let first = spec.to_functional().unwrap();
+        let second = spec.to_functional().unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let sigma = [0.01, 0.02, 0.03];
+        assert_eq!(first.gga_exc(&rho, &sigma), second.gga_exc(&rho, &sigma));
+        assert_eq!(first.gga_exc(&rho, &sigma), func.gga_exc(&rho, &sigma));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn spec_wrong_ext_params() {
+        use crate::functional::FunctionalSpec;
+
+        let n = Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized)
+            .unwrap()
+            .n_ext_params();
+        for count in &[1, n + 1] {
+            let json = format!(
+                r#"{{"number": {}, "polarization": "Unpolarized", "ext_params": [{}]}}"#,
+                ids::HYB_GGA_XC_HSE06,
+                vec!["0.1"; *count].join(", ")
+            );
+            let spec: FunctionalSpec = serde_json::from_str(&json).unwrap();
+            match spec.to_functional() {
+                Err(FunctionalError::InvalidExtParam(found)) => assert_eq!(found as usize, *count),
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn refresh_info() {
+        let mut func =
+            Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        let mut params = func.ext_params().to_vec();
+        params[0] *= 0.5;
+        func.set_ext_params(&params).unwrap();
+        func.refresh_info();
+        assert_eq!(func.number(), ids::HYB_GGA_XC_HSE06);
+        assert!(func.name().is_ok());
+        assert_eq!(func.family().unwrap(), FunctionalFamily::HybridGGA);
+    }
+
+    #[test]
+    fn all_dois() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let dois = func.all_dois();
+        assert!(!dois.is_empty());
+        assert!(dois.iter().all(|doi| !doi.is_empty()));
+        assert!(dois.iter().any(|doi| doi.starts_with("10.")));
+    }
+
+    #[test]
+    fn references() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        let references = func.references();
+        assert!(!references.is_empty());
+        assert!(references.iter().all(|r| !r.reference.is_empty()));
+    }
+
+    #[test]
+    fn citation_text() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        let citation = func.citation_text();
+        let primary = &func.references()[0];
+        assert!(citation.contains("Perdew"));
+        assert!(citation.contains("Phys. Rev. Lett."));
+        assert!(citation.contains(&primary.doi));
+        assert!(citation.contains("10.1103/"));
+    }
+
+    #[test]
+    fn report() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        let report = func.report();
+        assert!(report.contains(&func.name().unwrap()));
+        assert!(report.contains("Family: GGA"));
+        assert!(report.contains("HAVE_EXC"));
+        assert!(report.contains("10.1103/"));
+    }
+
+    #[test]
+    fn info() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let info = func.info().unwrap();
+        assert_eq!(info.number, 1);
+        assert_eq!(info.name, "Slater exchange");
+        assert_eq!(info.flags, func.flags());
+    }
+
+    #[test]
+    fn n_spin() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        assert_eq!(func.n_spin(), 2);
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.n_spin(), 1);
+    }
+
+    #[test]
+    fn output_len() {
+        let rho = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let exc = func.lda_exc(&rho);
+        let vxc = func.lda_vxc(&rho);
+        let fxc = func.lda_fxc(&rho);
+        assert_eq!(func.output_len(3, DerivativeOrder::Exc), exc.len());
+        assert_eq!(func.output_len(3, DerivativeOrder::Vxc), vxc.len());
+        assert_eq!(func.output_len(3, DerivativeOrder::Fxc), fxc.len());
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(
+            func.output_len(6, DerivativeOrder::Fxc),
+            func.lda_fxc(&rho).len()
+        );
+    }
+
+    #[test]
+    fn components() {
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap();
+        assert_eq!(func.vxc_components(), 2);
+        assert_eq!(func.vsigma_components(), 3);
+        assert_eq!(func.vtau_components(), 0);
+        assert_eq!(func.fxc_components(), 3);
+        assert_eq!(func.kxc_components(), 4);
+        let func = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.vsigma_components(), 1);
+    }
+
+    #[test]
+    fn input_layout() {
+        let scan = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Polarized).unwrap();
+        let layout = InputLayout {
+            rho: 2,
+            sigma: 3,
+            lapl: 0,
+            tau: 2,
+        };
+        assert_eq!(scan.input_layout(), layout);
+        let lda = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        let layout = InputLayout {
+            rho: 1,
+            sigma: 0,
+            lapl: 0,
+            tau: 0,
+        };
+        assert_eq!(lda.input_layout(), layout);
+    }
+
+    #[test]
+    fn is_development() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        assert!(!func.is_development());
+    }
+
     #[test]
     fn clone() {
         let func = Functional::from_id(1, Polarization::Polarized).unwrap();
         let cloned = func.clone();
-        assert_eq!(func.name(), cloned.name());
+        assert_eq!(func.name().unwrap(), cloned.name().unwrap());
+    }
+
+    #[test]
+    fn from_raw() {
+        let func = unsafe {
+            let xc_func = libxc_sys::xc_func_alloc();
+            assert_eq!(
+                libxc_sys::xc_func_init(xc_func, ids::LDA_X, Polarization::Unpolarized as i32),
+                0
+            );
+            Functional::from_raw(xc_func, Polarization::Unpolarized)
+        };
+        assert_eq!(func.number(), ids::LDA_X);
+        let expected = Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        assert_eq!(func.lda_exc(&rho), expected.lda_exc(&rho));
+        // Dropping the wrapper is the only release of the handle.
+        drop(func);
+    }
+
+    #[test]
+    fn hash_set() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap());
+        set.insert(Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap());
+        assert_eq!(set.len(), 1);
+        set.insert(Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap());
+        let mut hse =
+            Functional::from_id(ids::HYB_GGA_XC_HSE06, Polarization::Unpolarized).unwrap();
+        set.insert(hse.clone());
+        let mut params = hse.ext_params().to_vec();
+        params[0] *= 0.5;
+        hse.set_ext_params(&params).unwrap();
+        set.insert(hse);
+        assert_eq!(set.len(), 4);
+    }
+
+    #[test]
+    fn clone_independent() {
+        let mut func = Functional::from_id(ids::GGA_XC_VV10, Polarization::Unpolarized).unwrap();
+        let cloned = func.clone();
+        assert_eq!(func.nlc_coef(), cloned.nlc_coef());
+        let (b, c) = cloned.nlc_coef();
+        func.set_nlc_coef(2.0 * b, 2.0 * c);
+        assert_eq!(cloned.nlc_coef(), (b, c));
+        drop(func);
+        assert!(cloned.name().is_ok());
     }
 }
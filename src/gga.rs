@@ -0,0 +1,537 @@
+//! Evaluation of GGA functionals.
+//!
+//! Densities follow the LibXC layout: one value per point for unpolarized functionals and
+//! interleaved `(up, down)` pairs for polarized functionals. Contracted gradients `sigma` hold
+//! one value per point for unpolarized functionals and interleaved `(uu, ud, dd)` triples for
+//! polarized functionals.
+//!
+//! Empty inputs describe zero points and yield empty outputs without calling into LibXC.
+
+use std::f64::consts::PI;
+
+use libxc_sys;
+
+use crate::error::FunctionalError;
+use crate::flags;
+use crate::functional::{Functional, FunctionalFamily, Polarization};
+use crate::util;
+
+/// Local Lieb-Oxford bound on the exchange enhancement factor.
+pub const LIEB_OXFORD_BOUND: f64 = 1.804;
+
+/// Energy per particle and derivatives of a GGA functional up to third order.
+///
+/// Derivatives which the functional does not provide are `None`. Polarized derivatives hold one
+/// value per point for each unique combination of spin components, in the LibXC order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgaDerivatives {
+    pub exc: Option<Vec<f64>>,
+    pub vrho: Option<Vec<f64>>,
+    pub vsigma: Option<Vec<f64>>,
+    pub v2rho2: Option<Vec<f64>>,
+    pub v2rhosigma: Option<Vec<f64>>,
+    pub v2sigma2: Option<Vec<f64>>,
+    pub v3rho3: Option<Vec<f64>>,
+    pub v3rho2sigma: Option<Vec<f64>>,
+    pub v3rhosigma2: Option<Vec<f64>>,
+    pub v3sigma3: Option<Vec<f64>>,
+}
+
+impl Functional {
+    /// Evaluates every derivative of the energy up to third order which the functional provides
+    /// in a single LibXC call.
+    pub fn gga(&self, rho: &[f64], sigma: &[f64]) -> GgaDerivatives {
+        let npoints = self.gga_npoints(rho, sigma, flags::HAVE_EXC);
+        let polarized = self.polarization == Polarization::Polarized;
+        let buffer = |flag: i32, components: usize| {
+            if self.has_flag(flag) {
+                // Unpolarized derivatives hold a single value per point.
+                let components = if polarized { components } else { 1 };
+                Some(vec![0.0; npoints * components])
+            } else {
+                None
+            }
+        };
+        let mut exc = buffer(flags::HAVE_EXC, 1);
+        let mut vrho = buffer(flags::HAVE_VXC, 2);
+        let mut vsigma = buffer(flags::HAVE_VXC, 3);
+        let mut v2rho2 = buffer(flags::HAVE_FXC, 3);
+        let mut v2rhosigma = buffer(flags::HAVE_FXC, 6);
+        let mut v2sigma2 = buffer(flags::HAVE_FXC, 6);
+        let mut v3rho3 = buffer(flags::HAVE_KXC, 4);
+        let mut v3rho2sigma = buffer(flags::HAVE_KXC, 9);
+        let mut v3rhosigma2 = buffer(flags::HAVE_KXC, 12);
+        let mut v3sigma3 = buffer(flags::HAVE_KXC, 10);
+        // LibXC skips the derivatives whose buffer is null.
+        let ptr = |buffer: &mut Option<Vec<f64>>| match buffer {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => std::ptr::null_mut(),
+        };
+        let null = std::ptr::null_mut;
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_gga(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    ptr(&mut exc),
+                    ptr(&mut vrho),
+                    ptr(&mut vsigma),
+                    ptr(&mut v2rho2),
+                    ptr(&mut v2rhosigma),
+                    ptr(&mut v2sigma2),
+                    ptr(&mut v3rho3),
+                    ptr(&mut v3rho2sigma),
+                    ptr(&mut v3rhosigma2),
+                    ptr(&mut v3sigma3),
+                    // Fourth derivatives are not requested.
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                )
+            };
+        }
+        GgaDerivatives {
+            exc,
+            vrho,
+            vsigma,
+            v2rho2,
+            v2rhosigma,
+            v2sigma2,
+            v3rho3,
+            v3rho2sigma,
+            v3rhosigma2,
+            v3sigma3,
+        }
+    }
+
+    /// Evaluates the exchange-correlation energy per particle at each point.
+    pub fn gga_exc(&self, rho: &[f64], sigma: &[f64]) -> Vec<f64> {
+        let npoints = self.gga_npoints(rho, sigma, flags::HAVE_EXC);
+        let mut zk = vec![0.0; npoints];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_gga_exc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    zk.as_mut_ptr(),
+                )
+            };
+        }
+        zk
+    }
+
+    /// Evaluates the first derivatives of the energy with respect to `rho` and `sigma`.
+    pub fn gga_vxc(&self, rho: &[f64], sigma: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let npoints = self.gga_npoints(rho, sigma, flags::HAVE_VXC);
+        let mut vrho = vec![0.0; rho.len()];
+        let mut vsigma = vec![0.0; sigma.len()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_gga_vxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    vrho.as_mut_ptr(),
+                    vsigma.as_mut_ptr(),
+                )
+            };
+        }
+        (vrho, vsigma)
+    }
+
+    /// Evaluates the energy per particle, failing instead of panicking if the functional is
+    /// uninitialized or does not provide the energy through the GGA interface.
+    pub fn try_gga_exc(&self, rho: &[f64], sigma: &[f64]) -> Result<Vec<f64>, FunctionalError> {
+        self.check_evaluator(FunctionalFamily::GGA, flags::HAVE_EXC)?;
+        Ok(self.gga_exc(rho, sigma))
+    }
+
+    /// Evaluates the first derivatives of the energy, failing instead of panicking if the
+    /// functional is uninitialized or does not provide them through the GGA interface.
+    pub fn try_gga_vxc(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+    ) -> Result<(Vec<f64>, Vec<f64>), FunctionalError> {
+        self.check_evaluator(FunctionalFamily::GGA, flags::HAVE_VXC)?;
+        Ok(self.gga_vxc(rho, sigma))
+    }
+
+    /// Evaluates the energy per particle and its first derivatives in a single LibXC call.
+    pub fn gga_exc_vxc(&self, rho: &[f64], sigma: &[f64]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        // LibXC provides the energy of every functional that provides a potential.
+        let npoints = self.gga_npoints(rho, sigma, flags::HAVE_VXC);
+        let mut zk = vec![0.0; npoints];
+        let mut vrho = vec![0.0; rho.len()];
+        let mut vsigma = vec![0.0; sigma.len()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_gga_exc_vxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    zk.as_mut_ptr(),
+                    vrho.as_mut_ptr(),
+                    vsigma.as_mut_ptr(),
+                )
+            };
+        }
+        (zk, vrho, vsigma)
+    }
+
+    /// Evaluates the terms of the exchange-correlation potential used to assemble a GGA
+    /// Kohn-Sham matrix.
+    ///
+    /// Returns `vrho` along with the derivative of the energy with respect to the density
+    /// gradient, which is `2 * vsigma * grad` for unpolarized functionals. The gradient shares
+    /// the spin layout of the density.
+    pub fn gga_potential_terms(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        grad: &[[f64; 3]],
+    ) -> (Vec<f64>, Vec<[f64; 3]>) {
+        assert_eq!(
+            grad.len(),
+            rho.len(),
+            "gradient must have the layout of the density"
+        );
+        let (vrho, vsigma) = self.gga_vxc(rho, sigma);
+        let vgrad = self.gradient_terms(&vsigma, grad);
+        (vrho, vgrad)
+    }
+
+    /// Evaluates the derivative of the energy with respect to the density gradient, which is
+    /// `2 * vsigma * grad` for unpolarized functionals.
+    ///
+    /// The gradient shares the spin layout of the density and `sigma` is contracted from it.
+    pub fn gga_vgrad(&self, rho: &[f64], grad: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        let sigma = match self.polarization {
+            Polarization::Unpolarized => util::sigma_from_gradient(grad),
+            Polarization::Polarized => {
+                let up: Vec<[f64; 3]> = grad.iter().step_by(2).copied().collect();
+                let down: Vec<[f64; 3]> = grad.iter().skip(1).step_by(2).copied().collect();
+                util::sigma_from_gradient_polarized(&up, &down)
+            }
+        };
+        self.gga_potential_terms(rho, &sigma, grad).1
+    }
+
+    // Contracts `vsigma` with the density gradient into the derivative with respect to the
+    // gradient of each spin channel.
+    fn gradient_terms(&self, vsigma: &[f64], grad: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        let combine = |a: f64, u: &[f64; 3], b: f64, d: &[f64; 3]| {
+            [
+                a * u[0] + b * d[0],
+                a * u[1] + b * d[1],
+                a * u[2] + b * d[2],
+            ]
+        };
+        match self.polarization {
+            Polarization::Unpolarized => vsigma
+                .iter()
+                .zip(grad.iter())
+                .map(|(v, g)| combine(2.0 * v, g, 0.0, g))
+                .collect(),
+            Polarization::Polarized => vsigma
+                .chunks(3)
+                .zip(grad.chunks(2))
+                .flat_map(|(v, g)| {
+                    vec![
+                        combine(2.0 * v[0], &g[0], v[1], &g[1]),
+                        combine(2.0 * v[2], &g[1], v[1], &g[0]),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Evaluates the energy per particle of an unpolarized functional from the reduced gradient
+    /// `s = |grad rho| / (2 (3 pi^2)^(1/3) rho^(4/3))` at each point.
+    pub fn gga_exc_from_s(&self, rho: &[f64], s: &[f64]) -> Vec<f64> {
+        assert_eq!(
+            self.polarization,
+            Polarization::Unpolarized,
+            "a reduced gradient per point describes an unpolarized density"
+        );
+        assert_eq!(
+            s.len(),
+            rho.len(),
+            "one reduced gradient is required per point"
+        );
+        let sigma: Vec<f64> = rho
+            .iter()
+            .zip(s.iter())
+            .map(|(n, s)| util::sigma_from_s(*n, *s))
+            .collect();
+        self.gga_exc(rho, &sigma)
+    }
+
+    /// Evaluates the exchange enhancement factor `exc / ex_lda` of an unpolarized functional at
+    /// each point from the density and reduced gradient, which must be positive and non-negative.
+    pub fn enhancement_factor(&self, rho: &[f64], s: &[f64]) -> Vec<f64> {
+        self.gga_exc_from_s(rho, s)
+            .iter()
+            .zip(rho.iter())
+            .map(|(exc, n)| exc / lda_exchange(&[*n]))
+            .collect()
+    }
+
+    /// Returns the mean of the exchange enhancement factor over the given points, or `None` if
+    /// there are no points.
+    pub fn mean_enhancement_factor(&self, rho: &[f64], s: &[f64]) -> Option<f64> {
+        let factors = self.enhancement_factor(rho, s);
+        if factors.is_empty() {
+            return None;
+        }
+        Some(factors.iter().sum::<f64>() / factors.len() as f64)
+    }
+
+    /// Evaluates the energy per particle of an unpolarized functional at a single point.
+    pub fn gga_exc_point(&self, rho: f64, sigma: f64) -> f64 {
+        self.gga_exc(&[rho], &[sigma])[0]
+    }
+
+    /// Evaluates the energy per particle of a polarized functional at a single point.
+    pub fn gga_exc_point_polarized(&self, rho: (f64, f64), sigma: (f64, f64, f64)) -> f64 {
+        self.gga_exc(&[rho.0, rho.1], &[sigma.0, sigma.1, sigma.2])[0]
+    }
+
+    /// Evaluates the energy per particle and checks at each point whether it respects the local
+    /// Lieb-Oxford bound, `exc >= 1.804 * ex_lda`.
+    pub fn check_lieb_oxford(&self, rho: &[f64], sigma: &[f64]) -> Vec<bool> {
+        self.gga_exc_with_bound_flags(rho, sigma).1
+    }
+
+    /// Evaluates the energy per particle and returns it together with whether each point
+    /// respects the local Lieb-Oxford bound.
+    pub fn gga_exc_with_bound_flags(&self, rho: &[f64], sigma: &[f64]) -> (Vec<f64>, Vec<bool>) {
+        let nspin = self.n_spin();
+        let zk = self.gga_exc(rho, sigma);
+        let bounded = zk
+            .iter()
+            .zip(rho.chunks(nspin))
+            .map(|(exc, n)| *exc >= LIEB_OXFORD_BOUND * lda_exchange(n))
+            .collect();
+        (zk, bounded)
+    }
+
+    // Validates the input of a GGA evaluation and returns the number of points.
+    fn gga_npoints(&self, rho: &[f64], sigma: &[f64], flag: i32) -> usize {
+        if let Err(err) = self.check_evaluator(FunctionalFamily::GGA, flag) {
+            panic!("{}", err);
+        }
+        let nspin = self.n_spin();
+        assert_eq!(
+            rho.len() % nspin,
+            0,
+            "density length must be a multiple of the number of spin channels"
+        );
+        let npoints = rho.len() / nspin;
+        assert_eq!(
+            sigma.len(),
+            npoints * (2 * nspin - 1),
+            "sigma length does not match the number of points"
+        );
+        npoints
+    }
+}
+
+// Returns the LDA exchange energy per particle of a point with the given spin densities.
+fn lda_exchange(rho: &[f64]) -> f64 {
+    let total: f64 = rho.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    // Spin scaling: Ex[up, down] = (Ex[2 up] + Ex[2 down]) / 2.
+    let nspin = rho.len() as f64;
+    let scaled: f64 = rho.iter().map(|n| (nspin * n).powf(4.0 / 3.0)).sum::<f64>() / nspin;
+    -0.75 * (3.0 / PI).cbrt() * scaled / total
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flags;
+    use crate::functional::{Functional, Polarization};
+    use crate::util;
+
+    #[test]
+    fn gga() {
+        let func = Functional::from_id(101, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let sigma = [0.01, 0.005, 0.02, 0.03, 0.01, 0.04];
+        let derivatives = func.gga(&rho, &sigma);
+        let (exc, vrho, vsigma) = func.gga_exc_vxc(&rho, &sigma);
+        assert_eq!(derivatives.exc, Some(exc));
+        assert_eq!(derivatives.vrho, Some(vrho));
+        assert_eq!(derivatives.vsigma, Some(vsigma));
+        if func.has_flag(flags::HAVE_FXC) {
+            assert_eq!(derivatives.v2rho2.unwrap().len(), 6);
+            assert_eq!(derivatives.v2rhosigma.unwrap().len(), 12);
+            assert_eq!(derivatives.v2sigma2.unwrap().len(), 12);
+        } else {
+            assert_eq!(derivatives.v2rho2, None);
+        }
+        if !func.has_flag(flags::HAVE_KXC) {
+            assert_eq!(derivatives.v3sigma3, None);
+        }
+    }
+
+    #[test]
+    fn gga_exc() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let exc = func.gga_exc(&[0.1, 0.2], &[0.01, 0.02]);
+        assert_eq!(exc.len(), 2);
+        assert!(exc.iter().all(|e| *e < 0.0));
+    }
+
+    #[test]
+    fn gga_empty() {
+        let func = Functional::from_id(101, Polarization::Polarized).unwrap();
+        assert!(func.gga_exc(&[], &[]).is_empty());
+        let (vrho, vsigma) = func.gga_vxc(&[], &[]);
+        assert!(vrho.is_empty() && vsigma.is_empty());
+    }
+
+    #[test]
+    fn gga_vxc() {
+        let func = Functional::from_id(101, Polarization::Polarized).unwrap();
+        let (vrho, vsigma) = func.gga_vxc(&[0.1, 0.2], &[0.01, 0.005, 0.02]);
+        assert_eq!(vrho.len(), 2);
+        assert_eq!(vsigma.len(), 3);
+    }
+
+    #[test]
+    fn try_gga_exc() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let (rho, sigma) = ([0.1, 0.2], [0.01, 0.02]);
+        assert_eq!(
+            func.try_gga_exc(&rho, &sigma).unwrap(),
+            func.gga_exc(&rho, &sigma)
+        );
+        let lda = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert!(lda.try_gga_vxc(&rho, &sigma).is_err());
+    }
+
+    #[test]
+    fn gga_exc_vxc() {
+        let func = Functional::from_id(101, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2];
+        let sigma = [0.01, 0.005, 0.02];
+        let (exc, vrho, vsigma) = func.gga_exc_vxc(&rho, &sigma);
+        assert_eq!(exc, func.gga_exc(&rho, &sigma));
+        assert_eq!((vrho, vsigma), func.gga_vxc(&rho, &sigma));
+    }
+
+    #[test]
+    fn gga_potential_terms() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2];
+        let grad = [[0.1, 0.0, -0.05], [0.02, 0.03, 0.04]];
+        let sigma = util::sigma_from_gradient(&grad);
+        let (vrho, vsigma) = func.gga_vxc(&rho, &sigma);
+        let (terms_vrho, terms_vgrad) = func.gga_potential_terms(&rho, &sigma, &grad);
+        assert_eq!(terms_vrho, vrho);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert_eq!(terms_vgrad[i][j], 2.0 * vsigma[i] * grad[i][j]);
+            }
+        }
+    }
+
+    #[test]
+    fn gga_vgrad() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2];
+        let grad = [[0.1, 0.0, -0.05], [0.02, 0.03, 0.04]];
+        let sigma = util::sigma_from_gradient(&grad);
+        let (_, vsigma) = func.gga_vxc(&rho, &sigma);
+        let vgrad = func.gga_vgrad(&rho, &grad);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert_eq!(vgrad[i][j], 2.0 * vsigma[i] * grad[i][j]);
+            }
+        }
+    }
+
+    #[test]
+    fn check_lieb_oxford() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let mut rho = Vec::new();
+        let mut sigma = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let n = 0.01 * 2f64.powi(i);
+                rho.push(n);
+                sigma.push(util::sigma_from_s(n, 0.5 * j as f64));
+            }
+        }
+        assert!(func.check_lieb_oxford(&rho, &sigma).iter().all(|ok| *ok));
+    }
+
+    #[test]
+    fn gga_exc_with_bound_flags() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let rho: Vec<f64> = (0..10).map(|i| 0.01 * 2f64.powi(i)).collect();
+        let sigma: Vec<f64> = rho.iter().map(|n| util::sigma_from_s(*n, 1.5)).collect();
+        let (zk, bounded) = func.gga_exc_with_bound_flags(&rho, &sigma);
+        assert_eq!(zk, func.gga_exc(&rho, &sigma));
+        assert_eq!(bounded.len(), rho.len());
+        assert!(bounded.iter().all(|ok| *ok));
+    }
+
+    #[test]
+    fn lda_exchange() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.3, 0.1];
+        let exc = func.lda_exc(&rho)[0];
+        assert!((super::lda_exchange(&rho) - exc).abs() < 1e-10);
+    }
+
+    #[test]
+    fn gga_exc_from_s() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let s = [0.0, 0.5, 2.0];
+        let sigma: Vec<f64> = (0..3).map(|i| util::sigma_from_s(rho[i], s[i])).collect();
+        assert_eq!(func.gga_exc_from_s(&rho, &s), func.gga_exc(&rho, &sigma));
+    }
+
+    #[test]
+    fn mean_enhancement_factor() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let s = [0.0, 0.5, 1.0, 2.0];
+        let factors = func.enhancement_factor(&rho, &s);
+        // PBE exchange reduces to LDA exchange for a uniform density.
+        assert!((factors[0] - 1.0).abs() < 1e-10);
+        let mean = factors.iter().sum::<f64>() / 4.0;
+        assert!((func.mean_enhancement_factor(&rho, &s).unwrap() - mean).abs() < 1e-14);
+        assert_eq!(func.mean_enhancement_factor(&[], &[]), None);
+    }
+
+    #[test]
+    fn gga_exc_point() {
+        let func = Functional::from_id(101, Polarization::Unpolarized).unwrap();
+        let exc = func.gga_exc(&[0.3], &[0.05]);
+        assert_eq!(func.gga_exc_point(0.3, 0.05), exc[0]);
+    }
+
+    #[test]
+    fn gga_exc_point_polarized() {
+        let func = Functional::from_id(101, Polarization::Polarized).unwrap();
+        let exc = func.gga_exc(&[0.3, 0.1], &[0.05, 0.01, 0.02]);
+        assert_eq!(
+            func.gga_exc_point_polarized((0.3, 0.1), (0.05, 0.01, 0.02)),
+            exc[0]
+        );
+    }
+}
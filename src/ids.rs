@@ -0,0 +1,102 @@
+//! Identifiers of well-known functionals.
+//!
+//! The values are the `XC_*` definitions of the linked LibXC headers with the `XC_` prefix
+//! removed.
+
+pub const LDA_X: i32 = libxc_sys::XC_LDA_X as i32;
+pub const LDA_C_VWN: i32 = libxc_sys::XC_LDA_C_VWN as i32;
+pub const LDA_C_VWN_RPA: i32 = libxc_sys::XC_LDA_C_VWN_RPA as i32;
+pub const LDA_C_PZ: i32 = libxc_sys::XC_LDA_C_PZ as i32;
+pub const LDA_C_PW: i32 = libxc_sys::XC_LDA_C_PW as i32;
+pub const LDA_C_PW_MOD: i32 = libxc_sys::XC_LDA_C_PW_MOD as i32;
+pub const LDA_X_2D: i32 = libxc_sys::XC_LDA_X_2D as i32;
+pub const LDA_K_TF: i32 = libxc_sys::XC_LDA_K_TF as i32;
+pub const GGA_X_GAM: i32 = libxc_sys::XC_GGA_X_GAM as i32;
+pub const GGA_X_PBE: i32 = libxc_sys::XC_GGA_X_PBE as i32;
+pub const GGA_X_PBE_R: i32 = libxc_sys::XC_GGA_X_PBE_R as i32;
+pub const GGA_X_B88: i32 = libxc_sys::XC_GGA_X_B88 as i32;
+pub const GGA_X_PW91: i32 = libxc_sys::XC_GGA_X_PW91 as i32;
+pub const GGA_X_PBE_SOL: i32 = libxc_sys::XC_GGA_X_PBE_SOL as i32;
+pub const GGA_X_RPBE: i32 = libxc_sys::XC_GGA_X_RPBE as i32;
+pub const GGA_C_PBE: i32 = libxc_sys::XC_GGA_C_PBE as i32;
+pub const GGA_C_LYP: i32 = libxc_sys::XC_GGA_C_LYP as i32;
+pub const GGA_C_P86: i32 = libxc_sys::XC_GGA_C_P86 as i32;
+pub const GGA_C_PBE_SOL: i32 = libxc_sys::XC_GGA_C_PBE_SOL as i32;
+pub const GGA_C_PW91: i32 = libxc_sys::XC_GGA_C_PW91 as i32;
+pub const GGA_XC_VV10: i32 = libxc_sys::XC_GGA_XC_VV10 as i32;
+pub const MGGA_X_TPSS: i32 = libxc_sys::XC_MGGA_X_TPSS as i32;
+pub const MGGA_X_M06_L: i32 = libxc_sys::XC_MGGA_X_M06_L as i32;
+pub const MGGA_C_TPSS: i32 = libxc_sys::XC_MGGA_C_TPSS as i32;
+pub const MGGA_X_SCAN: i32 = libxc_sys::XC_MGGA_X_SCAN as i32;
+pub const MGGA_C_SCAN: i32 = libxc_sys::XC_MGGA_C_SCAN as i32;
+pub const MGGA_X_SCANL: i32 = libxc_sys::XC_MGGA_X_SCANL as i32;
+pub const MGGA_C_SCANL: i32 = libxc_sys::XC_MGGA_C_SCANL as i32;
+pub const HYB_GGA_XC_B3PW91: i32 = libxc_sys::XC_HYB_GGA_XC_B3PW91 as i32;
+pub const HYB_GGA_XC_B3LYP: i32 = libxc_sys::XC_HYB_GGA_XC_B3LYP as i32;
+pub const HYB_GGA_XC_PBEH: i32 = libxc_sys::XC_HYB_GGA_XC_PBEH as i32;
+pub const HYB_GGA_XC_HSE03: i32 = libxc_sys::XC_HYB_GGA_XC_HSE03 as i32;
+pub const HYB_GGA_XC_HSE06: i32 = libxc_sys::XC_HYB_GGA_XC_HSE06 as i32;
+pub const HYB_GGA_XC_CAM_B3LYP: i32 = libxc_sys::XC_HYB_GGA_XC_CAM_B3LYP as i32;
+pub const HYB_GGA_XC_LC_VV10: i32 = libxc_sys::XC_HYB_GGA_XC_LC_VV10 as i32;
+pub const HYB_MGGA_X_SCAN0: i32 = libxc_sys::XC_HYB_MGGA_X_SCAN0 as i32;
+pub const HYB_MGGA_XC_TPSSH: i32 = libxc_sys::XC_HYB_MGGA_XC_TPSSH as i32;
+
+/// Every identifier defined in this module.
+pub const ALL: &[i32] = &[
+    LDA_X,
+    LDA_C_VWN,
+    LDA_C_VWN_RPA,
+    LDA_C_PZ,
+    LDA_C_PW,
+    LDA_C_PW_MOD,
+    LDA_X_2D,
+    LDA_K_TF,
+    GGA_X_GAM,
+    GGA_X_PBE,
+    GGA_X_PBE_R,
+    GGA_X_B88,
+    GGA_X_PW91,
+    GGA_X_PBE_SOL,
+    GGA_X_RPBE,
+    GGA_C_PBE,
+    GGA_C_LYP,
+    GGA_C_P86,
+    GGA_C_PBE_SOL,
+    GGA_C_PW91,
+    GGA_XC_VV10,
+    MGGA_X_TPSS,
+    MGGA_X_M06_L,
+    MGGA_C_TPSS,
+    MGGA_X_SCAN,
+    MGGA_C_SCAN,
+    MGGA_X_SCANL,
+    MGGA_C_SCANL,
+    HYB_GGA_XC_B3PW91,
+    HYB_GGA_XC_B3LYP,
+    HYB_GGA_XC_PBEH,
+    HYB_GGA_XC_HSE03,
+    HYB_GGA_XC_HSE06,
+    HYB_GGA_XC_CAM_B3LYP,
+    HYB_GGA_XC_LC_VV10,
+    HYB_MGGA_X_SCAN0,
+    HYB_MGGA_XC_TPSSH,
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::functional::{Functional, Polarization};
+    use crate::ids;
+
+    #[test]
+    fn lda_x() {
+        assert_eq!(ids::LDA_X, 1);
+    }
+
+    #[test]
+    fn all() {
+        for id in ids::ALL {
+            let func = Functional::from_id(*id, Polarization::Unpolarized).unwrap();
+            assert_eq!(func.number(), *id);
+        }
+    }
+}
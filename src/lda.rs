@@ -0,0 +1,977 @@
+//! Evaluation of LDA functionals.
+//!
+//! Densities follow the LibXC layout: one value per point for unpolarized functionals and
+//! interleaved `(up, down)` pairs for polarized functionals.
+//!
+//! An empty density is a valid grid of zero points: evaluators return empty outputs without
+//! calling into LibXC.
+
+use std::time::{Duration, Instant};
+
+use libxc_sys;
+#[cfg(feature = "ndarray")]
+use ndarray::Array3;
+#[cfg(feature = "complex")]
+use num_complex::Complex64;
+
+use crate::aligned::AlignedVec;
+use crate::error::FunctionalError;
+use crate::flags;
+use crate::functional::{DerivativeOrder, Functional, FunctionalFamily, Polarization};
+use crate::util;
+
+/// Exchange-correlation potential separated into spin channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpinPotential {
+    /// Potential of the up channel, or of the total density for unpolarized functionals.
+    pub up: Vec<f64>,
+    /// Potential of the down channel, empty for unpolarized functionals.
+    pub down: Vec<f64>,
+}
+
+/// Energy per particle and derivatives of an LDA functional up to third order.
+///
+/// Derivatives which the functional does not provide are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LdaDerivatives {
+    pub exc: Option<Vec<f64>>,
+    pub vxc: Option<Vec<f64>>,
+    pub fxc: Option<Vec<f64>>,
+    pub kxc: Option<Vec<f64>>,
+}
+
+/// Energy and potential of an LDA functional over a grid in the forms derived from a single
+/// evaluation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LdaResult {
+    /// Energy per particle at each point.
+    pub exc_per_particle: Vec<f64>,
+    /// Energy per unit volume at each point, the energy per particle times the total density.
+    pub exc_density: Vec<f64>,
+    /// First derivative of the energy with respect to the density, in the layout of the density.
+    pub vxc: Vec<f64>,
+    /// Energy integrated over the grid with the quadrature weights.
+    pub integrated_energy: f64,
+}
+
+impl Functional {
+    /// Evaluates every derivative of the energy up to third order which the functional provides
+    /// in a single LibXC call.
+    pub fn lda(&self, rho: &[f64]) -> LdaDerivatives {
+        let npoints = self.lda_npoints(rho, flags::HAVE_EXC);
+        let buffer = |flag: i32, order: DerivativeOrder| {
+            if self.has_flag(flag) {
+                Some(vec![0.0; self.output_len(npoints, order)])
+            } else {
+                None
+            }
+        };
+        let mut exc = buffer(flags::HAVE_EXC, DerivativeOrder::Exc);
+        let mut vxc = buffer(flags::HAVE_VXC, DerivativeOrder::Vxc);
+        let mut fxc = buffer(flags::HAVE_FXC, DerivativeOrder::Fxc);
+        let mut kxc = buffer(flags::HAVE_KXC, DerivativeOrder::Kxc);
+        // LibXC skips the derivatives whose buffer is null.
+        let ptr = |buffer: &mut Option<Vec<f64>>| match buffer {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => std::ptr::null_mut(),
+        };
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    ptr(&mut exc),
+                    ptr(&mut vxc),
+                    ptr(&mut fxc),
+                    ptr(&mut kxc),
+                    std::ptr::null_mut(),
+                )
+            };
+        }
+        LdaDerivatives { exc, vxc, fxc, kxc }
+    }
+
+    /// Evaluates the exchange-correlation energy per particle at each point.
+    pub fn lda_exc(&self, rho: &[f64]) -> Vec<f64> {
+        let npoints = self.lda_npoints(rho, flags::HAVE_EXC);
+        let mut zk = vec![0.0; npoints];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_exc(self.xc_func, npoints as _, rho.as_ptr(), zk.as_mut_ptr())
+            };
+        }
+        zk
+    }
+
+    /// Evaluates the energy per particle, failing instead of panicking if the functional is
+    /// uninitialized or does not provide the energy through the LDA interface.
+    pub fn try_lda_exc(&self, rho: &[f64]) -> Result<Vec<f64>, FunctionalError> {
+        self.check_evaluator(FunctionalFamily::LDA, flags::HAVE_EXC)?;
+        Ok(self.lda_exc(rho))
+    }
+
+    /// Evaluates the first derivative of the energy, failing instead of panicking if the
+    /// functional is uninitialized or does not provide it through the LDA interface.
+    pub fn try_lda_vxc(&self, rho: &[f64]) -> Result<Vec<f64>, FunctionalError> {
+        self.check_evaluator(FunctionalFamily::LDA, flags::HAVE_VXC)?;
+        Ok(self.lda_vxc(rho))
+    }
+
+    /// Evaluates the second derivative of the energy, failing instead of panicking if the
+    /// functional is uninitialized or does not provide it through the LDA interface.
+    pub fn try_lda_fxc(&self, rho: &[f64]) -> Result<Vec<f64>, FunctionalError> {
+        self.check_evaluator(FunctionalFamily::LDA, flags::HAVE_FXC)?;
+        Ok(self.lda_fxc(rho))
+    }
+
+    /// Evaluates the energy per particle and fails if LibXC produces a NaN or infinite value.
+    pub fn lda_exc_validated(&self, rho: &[f64]) -> Result<Vec<f64>, FunctionalError> {
+        check_finite(self.lda_exc(rho))
+    }
+
+    /// Evaluates the energy per particle of a polarized functional from `(up, down)` pairs.
+    pub fn lda_exc_pairs(&self, rho: &[(f64, f64)]) -> Vec<f64> {
+        assert_eq!(
+            self.polarization,
+            Polarization::Polarized,
+            "spin pairs require a polarized functional"
+        );
+        let interleaved: Vec<f64> = rho.iter().flat_map(|(up, down)| vec![*up, *down]).collect();
+        self.lda_exc(&interleaved)
+    }
+
+    /// Evaluates the energy per particle only at points whose total density exceeds `threshold`.
+    ///
+    /// Returns `(index, exc)` pairs where `index` is the position of the point in the grid.
+    pub fn lda_exc_sparse(&self, rho: &[f64], threshold: f64) -> Vec<(usize, f64)> {
+        self.lda_npoints(rho, flags::HAVE_EXC);
+        let nspin = self.n_spin();
+        let (indices, points): (Vec<usize>, Vec<&[f64]>) = rho
+            .chunks(nspin)
+            .enumerate()
+            .filter(|(_, n)| n.iter().sum::<f64>() > threshold)
+            .unzip();
+        if indices.is_empty() {
+            return Vec::new();
+        }
+        let gathered: Vec<f64> = points.concat();
+        indices.into_iter().zip(self.lda_exc(&gathered)).collect()
+    }
+
+    /// Evaluates the energy per particle and measures the wall time spent inside LibXC.
+    ///
+    /// The output buffer is allocated before the timer starts.
+    pub fn lda_exc_timed(&self, rho: &[f64]) -> (Vec<f64>, Duration) {
+        let npoints = self.lda_npoints(rho, flags::HAVE_EXC);
+        let mut zk = vec![0.0; npoints];
+        let start = Instant::now();
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_exc(self.xc_func, npoints as _, rho.as_ptr(), zk.as_mut_ptr())
+            };
+        }
+        (zk, start.elapsed())
+    }
+
+    /// Evaluates the energy per particle of an unpolarized functional over a 3D grid, preserving
+    /// the shape of the grid.
+    #[cfg(feature = "ndarray")]
+    pub fn lda_exc_grid3d(&self, rho: &Array3<f64>) -> Array3<f64> {
+        assert_eq!(
+            self.polarization,
+            Polarization::Unpolarized,
+            "3D grids hold one density per point"
+        );
+        // Iteration follows the logical order of the grid regardless of its memory layout.
+        let flat: Vec<f64> = rho.iter().copied().collect();
+        Array3::from_shape_vec(rho.raw_dim(), self.lda_exc(&flat))
+            .expect("one energy is produced per grid point")
+    }
+
+    /// Evaluates the energy per particle into a buffer aligned for SIMD loads.
+    pub fn lda_exc_aligned(&self, rho: &[f64]) -> AlignedVec<f64> {
+        let npoints = self.lda_npoints(rho, flags::HAVE_EXC);
+        let mut zk = AlignedVec::new(npoints);
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_exc(self.xc_func, npoints as _, rho.as_ptr(), zk.as_mut_ptr())
+            };
+        }
+        zk
+    }
+
+    /// Evaluates the energy per particle in consecutive blocks of at most `block` points.
+    pub fn lda_exc_blocked(&self, rho: &[f64], block: usize) -> Vec<f64> {
+        assert!(block > 0, "block size must be positive");
+        let npoints = self.lda_npoints(rho, flags::HAVE_EXC);
+        let nspin = self.n_spin();
+        let mut zk = vec![0.0; npoints];
+        for (rho_block, zk_block) in rho.chunks(block * nspin).zip(zk.chunks_mut(block)) {
+            unsafe {
+                libxc_sys::xc_lda_exc(
+                    self.xc_func,
+                    zk_block.len() as _,
+                    rho_block.as_ptr(),
+                    zk_block.as_mut_ptr(),
+                )
+            };
+        }
+        zk
+    }
+
+    /// Lazily evaluates the energy per particle of each chunk of density as it is yielded.
+    ///
+    /// Every chunk must hold whole points, so polarized chunks have an even length.
+    pub fn lda_exc_stream<'a, I>(&'a self, chunks: I) -> impl Iterator<Item = Vec<f64>> + 'a
+    where
+        I: Iterator<Item = Vec<f64>> + 'a,
+    {
+        chunks.map(move |chunk| self.lda_exc(&chunk))
+    }
+
+    /// Evaluates the energy per particle at points spaced `stride` elements apart.
+    ///
+    /// The density of each point occupies the first one (unpolarized) or two (polarized)
+    /// elements of its stride. The points are gathered into a contiguous buffer before
+    /// evaluation.
+    pub fn lda_exc_strided(&self, rho: &[f64], stride: usize) -> Vec<f64> {
+        let nspin = self.n_spin();
+        assert!(
+            stride >= nspin,
+            "stride must cover the spin channels of a point"
+        );
+        let gathered: Vec<f64> = rho
+            .chunks(stride)
+            .filter_map(|point| point.get(..nspin))
+            .flatten()
+            .copied()
+            .collect();
+        self.lda_exc(&gathered)
+    }
+
+    /// Evaluates the first derivative of the energy with respect to the density at each point.
+    pub fn lda_vxc(&self, rho: &[f64]) -> Vec<f64> {
+        let npoints = self.lda_npoints(rho, flags::HAVE_VXC);
+        let mut vrho = vec![0.0; npoints * self.n_spin()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_vxc(self.xc_func, npoints as _, rho.as_ptr(), vrho.as_mut_ptr())
+            };
+        }
+        vrho
+    }
+
+    /// Evaluates the energy per particle and its first derivative in a single LibXC call.
+    pub fn lda_exc_vxc(&self, rho: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        // LibXC provides the energy of every functional that provides a potential.
+        let npoints = self.lda_npoints(rho, flags::HAVE_VXC);
+        let mut zk = vec![0.0; npoints];
+        let mut vrho = vec![0.0; rho.len()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_exc_vxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    zk.as_mut_ptr(),
+                    vrho.as_mut_ptr(),
+                )
+            };
+        }
+        (zk, vrho)
+    }
+
+    /// Evaluates the second derivative of the energy with respect to the density at each point.
+    ///
+    /// Polarized functionals return packed `(uu, ud, dd)` triples.
+    pub fn lda_fxc(&self, rho: &[f64]) -> Vec<f64> {
+        let npoints = self.lda_npoints(rho, flags::HAVE_FXC);
+        let nspin = self.n_spin();
+        let mut v2rho2 = vec![0.0; npoints * (2 * nspin - 1)];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_fxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    v2rho2.as_mut_ptr(),
+                )
+            };
+        }
+        v2rho2
+    }
+
+    /// Evaluates the third derivative of the energy with respect to the density at each point.
+    ///
+    /// Polarized functionals return packed `(uuu, uud, udd, ddd)` quadruples.
+    pub fn lda_kxc(&self, rho: &[f64]) -> Vec<f64> {
+        let npoints = self.lda_npoints(rho, flags::HAVE_KXC);
+        let mut v3rho3 = vec![0.0; self.output_len(npoints, DerivativeOrder::Kxc)];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_lda_kxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    v3rho3.as_mut_ptr(),
+                )
+            };
+        }
+        v3rho3
+    }
+
+    /// Contracts the exchange-correlation kernel with a perturbation of the density.
+    ///
+    /// The perturbation shares the layout of the density and the result is the first-order
+    /// response of the potential in the same layout.
+    pub fn fxc_dot(&self, rho: &[f64], drho: &[f64]) -> Vec<f64> {
+        assert_eq!(
+            drho.len(),
+            rho.len(),
+            "perturbation must have the layout of the density"
+        );
+        let fxc = self.lda_fxc(rho);
+        self.contract_fxc(&fxc, drho)
+    }
+
+    /// Contracts the exchange-correlation kernel with a complex perturbation of the density.
+    ///
+    /// The real and imaginary parts of the perturbation are contracted separately with the real
+    /// kernel of the ground-state density.
+    #[cfg(feature = "complex")]
+    pub fn fxc_dot_complex(&self, rho: &[f64], drho: &[Complex64]) -> Vec<Complex64> {
+        assert_eq!(
+            drho.len(),
+            rho.len(),
+            "perturbation must have the layout of the density"
+        );
+        let fxc = self.lda_fxc(rho);
+        let re: Vec<f64> = drho.iter().map(|d| d.re).collect();
+        let im: Vec<f64> = drho.iter().map(|d| d.im).collect();
+        self.contract_fxc(&fxc, &re)
+            .into_iter()
+            .zip(self.contract_fxc(&fxc, &im))
+            .map(|(re, im)| Complex64::new(re, im))
+            .collect()
+    }
+
+    /// Approximates the second derivative of the energy by central differences of [lda_vxc]
+    /// with step `h`, in the packed layout of [lda_fxc].
+    ///
+    /// Every spin density must be at least `h` so that no shifted density is negative.
+    ///
+    /// [lda_vxc]: Functional::lda_vxc
+    /// [lda_fxc]: Functional::lda_fxc
+    pub fn fxc_numeric(&self, rho: &[f64], h: f64) -> Vec<f64> {
+        assert!(h > 0.0, "finite difference step must be positive");
+        let nspin = self.n_spin();
+        // Derivatives of the potential with respect to the density of `channel` at every point.
+        let derivative = |channel: usize| {
+            let shifted = |delta: f64| {
+                let mut shifted = rho.to_vec();
+                for point in shifted.chunks_mut(nspin) {
+                    point[channel] += delta;
+                }
+                self.lda_vxc(&shifted)
+            };
+            shifted(h)
+                .iter()
+                .zip(shifted(-h))
+                .map(|(plus, minus)| (plus - minus) / (2.0 * h))
+                .collect::<Vec<f64>>()
+        };
+        match self.polarization {
+            Polarization::Unpolarized => derivative(0),
+            Polarization::Polarized => derivative(0)
+                .chunks(2)
+                .zip(derivative(1).chunks(2))
+                .flat_map(|(up, down)| vec![up[0], up[1], down[1]])
+                .collect(),
+        }
+    }
+
+    /// Evaluates the same-spin diagonal of the exchange-correlation kernel at each point.
+    ///
+    /// Polarized functionals return `(uu, dd)` pairs in the layout of the density.
+    pub fn fxc_diagonal(&self, rho: &[f64]) -> Vec<f64> {
+        let fxc = self.lda_fxc(rho);
+        match self.polarization {
+            Polarization::Unpolarized => fxc,
+            Polarization::Polarized => fxc.chunks(3).flat_map(|f| vec![f[0], f[2]]).collect(),
+        }
+    }
+
+    // Contracts a packed kernel with a perturbation in the layout of the density.
+    fn contract_fxc(&self, fxc: &[f64], drho: &[f64]) -> Vec<f64> {
+        match self.polarization {
+            Polarization::Unpolarized => fxc.iter().zip(drho.iter()).map(|(f, d)| f * d).collect(),
+            Polarization::Polarized => fxc
+                .chunks(3)
+                .zip(drho.chunks(2))
+                .flat_map(|(f, d)| vec![f[0] * d[0] + f[1] * d[1], f[1] * d[0] + f[2] * d[1]])
+                .collect(),
+        }
+    }
+
+    /// Evaluates the first derivative of the energy and splits it into spin channels.
+    pub fn lda_vxc_spin(&self, rho: &[f64]) -> SpinPotential {
+        let vrho = self.lda_vxc(rho);
+        match self.polarization {
+            Polarization::Unpolarized => SpinPotential {
+                up: vrho,
+                down: Vec::new(),
+            },
+            Polarization::Polarized => SpinPotential {
+                up: vrho.iter().step_by(2).copied().collect(),
+                down: vrho.iter().skip(1).step_by(2).copied().collect(),
+            },
+        }
+    }
+
+    /// Evaluates the first derivative of the energy multiplied by the density of each spin channel.
+    pub fn vxc_times_rho(&self, rho: &[f64]) -> Vec<f64> {
+        // The potential shares the spin layout of the density.
+        self.lda_vxc(rho)
+            .iter()
+            .zip(rho.iter())
+            .map(|(v, n)| v * n)
+            .collect()
+    }
+
+    /// Evaluates the energy and potential in a single LibXC call and integrates the energy over
+    /// the grid with the given quadrature weights.
+    pub fn lda_full(&self, rho: &[f64], weights: &[f64]) -> LdaResult {
+        let (exc_per_particle, vxc) = self.lda_exc_vxc(rho);
+        assert_eq!(
+            weights.len(),
+            exc_per_particle.len(),
+            "one quadrature weight is required per point"
+        );
+        let exc_density: Vec<f64> = exc_per_particle
+            .iter()
+            .zip(util::total_density(rho, self.polarization))
+            .map(|(e, n)| e * n)
+            .collect();
+        let integrated_energy = exc_density
+            .iter()
+            .zip(weights.iter())
+            .map(|(e, w)| e * w)
+            .sum();
+        LdaResult {
+            exc_per_particle,
+            exc_density,
+            vxc,
+            integrated_energy,
+        }
+    }
+
+    /// Integrates the exchange-correlation energy over a grid with the given quadrature weights.
+    pub fn total_energy(&self, rho: &[f64], weights: &[f64]) -> f64 {
+        let exc = self.lda_exc(rho);
+        self.integrate_energy(&exc, rho, weights)
+    }
+
+    /// Integrates the exchange-correlation energy of the coordinate-scaled density
+    /// `lambda^3 rho(lambda r)` over a grid with the given quadrature weights.
+    ///
+    /// Scaling maps each point to `r / lambda`, so the density is multiplied by `lambda^3` and the
+    /// weights are divided by it. Exchange energies scale linearly in `lambda`.
+    pub fn scaled_energy(&self, rho: &[f64], lambda: f64, weights: &[f64]) -> f64 {
+        let volume = lambda.powi(3);
+        let rho: Vec<f64> = rho.iter().map(|n| n * volume).collect();
+        let weights: Vec<f64> = weights.iter().map(|w| w / volume).collect();
+        self.total_energy(&rho, &weights)
+    }
+
+    /// Integrates `rho * (vxc - exc)` over a grid with the given quadrature weights.
+    ///
+    /// For exchange functionals homogeneous of degree 4/3 in the density, such as Slater
+    /// exchange, this equals one third of the exchange energy.
+    pub fn virial_energy(&self, rho: &[f64], weights: &[f64]) -> f64 {
+        let energy = self.total_energy(rho, weights);
+        let nspin = self.n_spin();
+        let potential: f64 = self
+            .vxc_times_rho(rho)
+            .chunks(nspin)
+            .zip(weights.iter())
+            .map(|(v, w)| v.iter().sum::<f64>() * w)
+            .sum();
+        potential - energy
+    }
+
+    /// Integrates the exchange-correlation energy over a batch of grid points and adds it to `acc`.
+    pub fn accumulate_energy(&self, rho: &[f64], weights: &[f64], acc: &mut f64) {
+        *acc += self.total_energy(rho, weights);
+    }
+
+    /// Evaluates the energy per particle and integrates it over the grid in a single pass.
+    pub fn lda_exc_and_energy(&self, rho: &[f64], weights: &[f64]) -> (Vec<f64>, f64) {
+        let exc = self.lda_exc(rho);
+        let energy = self.integrate_energy(&exc, rho, weights);
+        (exc, energy)
+    }
+
+    /// Integrates the exchange-correlation energy over a grid and partitions it between the up
+    /// and down spin channels.
+    ///
+    /// The energy density at each point is split in proportion to `rho_s * vxc_s` of each channel.
+    /// Unpolarized functionals split the energy evenly.
+    pub fn lda_exc_per_spin(&self, rho: &[f64], weights: &[f64]) -> (f64, f64) {
+        let (exc, vrho) = self.lda_exc_vxc(rho);
+        assert_eq!(
+            weights.len(),
+            exc.len(),
+            "one quadrature weight is required per point"
+        );
+        let nspin = self.n_spin();
+        let mut up = 0.0;
+        let mut down = 0.0;
+        for (((e, n), v), w) in exc
+            .iter()
+            .zip(rho.chunks(nspin))
+            .zip(vrho.chunks(nspin))
+            .zip(weights.iter())
+        {
+            let energy = e * n.iter().sum::<f64>() * w;
+            let fraction = match self.polarization {
+                Polarization::Unpolarized => 0.5,
+                Polarization::Polarized => {
+                    let total = n[0] * v[0] + n[1] * v[1];
+                    if total == 0.0 {
+                        0.5
+                    } else {
+                        n[0] * v[0] / total
+                    }
+                }
+            };
+            up += energy * fraction;
+            down += energy * (1.0 - fraction);
+        }
+        (up, down)
+    }
+
+    // Sums the energy per particle times the total density and weight of each point.
+    fn integrate_energy(&self, exc: &[f64], rho: &[f64], weights: &[f64]) -> f64 {
+        assert_eq!(
+            weights.len(),
+            exc.len(),
+            "one quadrature weight is required per point"
+        );
+        let nspin = self.n_spin();
+        exc.iter()
+            .zip(rho.chunks(nspin))
+            .zip(weights.iter())
+            .map(|((e, n), w)| e * n.iter().sum::<f64>() * w)
+            .sum()
+    }
+
+    // Validates the input of an LDA evaluation and returns the number of points.
+    fn lda_npoints(&self, rho: &[f64], flag: i32) -> usize {
+        if let Err(err) = self.check_evaluator(FunctionalFamily::LDA, flag) {
+            panic!("{}", err);
+        }
+        let nspin = self.n_spin();
+        assert_eq!(
+            rho.len() % nspin,
+            0,
+            "density length must be a multiple of the number of spin channels"
+        );
+        rho.len() / nspin
+    }
+}
+
+// Returns the output unchanged if every value is finite.
+fn check_finite(output: Vec<f64>) -> Result<Vec<f64>, FunctionalError> {
+    match output.iter().position(|value| !value.is_finite()) {
+        Some(index) => Err(FunctionalError::NonFiniteOutput(index)),
+        None => Ok(output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aligned::ALIGNMENT;
+    use crate::error::FunctionalError;
+    use crate::flags;
+    use crate::functional::{Functional, Polarization};
+    use crate::ids;
+
+    #[test]
+    fn lda() {
+        let func = Functional::from_id(ids::LDA_X, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let derivatives = func.lda(&rho);
+        assert_eq!(derivatives.exc, Some(func.lda_exc(&rho)));
+        assert_eq!(derivatives.vxc, Some(func.lda_vxc(&rho)));
+        assert_eq!(derivatives.fxc, Some(func.lda_fxc(&rho)));
+        if func.has_flag(flags::HAVE_KXC) {
+            assert_eq!(derivatives.kxc, Some(func.lda_kxc(&rho)));
+        } else {
+            assert_eq!(derivatives.kxc, None);
+        }
+    }
+
+    #[test]
+    fn lda_exc() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let exc = func.lda_exc(&[0.1, 0.2, 0.3]);
+        assert_eq!(exc.len(), 3);
+        assert!(exc.iter().all(|e| *e < 0.0));
+    }
+
+    #[test]
+    fn lda_empty() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        assert!(func.lda_exc(&[]).is_empty());
+        assert!(func.lda_vxc(&[]).is_empty());
+        assert!(func.lda_fxc(&[]).is_empty());
+        assert!(func.lda_exc_aligned(&[]).is_empty());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn lda_exc_grid3d() {
+        use ndarray::Array3;
+
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho =
+            Array3::from_shape_fn((2, 3, 4), |(i, j, k)| 0.01 * (1 + i + 2 * j + 6 * k) as f64);
+        let flat: Vec<f64> = rho.iter().copied().collect();
+        let exc = func.lda_exc_grid3d(&rho);
+        assert_eq!(exc.shape(), rho.shape());
+        for (e, expected) in exc.iter().zip(func.lda_exc(&flat)) {
+            assert_eq!(*e, expected);
+        }
+        assert_eq!(exc[[1, 2, 3]], func.lda_exc(&[rho[[1, 2, 3]]])[0]);
+    }
+
+    #[test]
+    fn lda_exc_aligned() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4, 0.5];
+        let exc = func.lda_exc_aligned(&rho);
+        assert_eq!(exc.as_ptr() as usize % ALIGNMENT, 0);
+        assert_eq!(&exc[..], &func.lda_exc(&rho)[..]);
+    }
+
+    #[test]
+    fn try_lda_exc() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2];
+        assert_eq!(func.try_lda_exc(&rho).unwrap(), func.lda_exc(&rho));
+        let pbe = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        match pbe.try_lda_vxc(&rho) {
+            Err(FunctionalError::UnsupportedFamily(_)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn try_lda_exc_uninitialized() {
+        let func = Functional {
+            xc_func: std::ptr::null_mut(),
+            xc_info: std::ptr::null(),
+            id: ids::LDA_X,
+            polarization: Polarization::Unpolarized,
+            ext_params: Vec::new(),
+            dens_threshold: 0.0,
+        };
+        match func.try_lda_exc(&[0.1]) {
+            Err(FunctionalError::Uninitialized) => (),
+            _ => panic!(),
+        }
+        assert!(func.try_lda_fxc(&[0.1]).is_err());
+    }
+
+    #[test]
+    fn lda_exc_validated() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.0, 0.3];
+        assert_eq!(func.lda_exc_validated(&rho).unwrap(), func.lda_exc(&rho));
+        match super::check_finite(vec![-0.5, f64::NAN, f64::INFINITY]) {
+            Err(FunctionalError::NonFiniteOutput(1)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn lda_exc_pairs() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let pairs = [(0.1, 0.2), (0.3, 0.4), (0.5, 0.0)];
+        let interleaved = [0.1, 0.2, 0.3, 0.4, 0.5, 0.0];
+        assert_eq!(func.lda_exc_pairs(&pairs), func.lda_exc(&interleaved));
+    }
+
+    #[test]
+    #[should_panic]
+    fn lda_exc_pairs_unpolarized() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        func.lda_exc_pairs(&[(0.1, 0.2)]);
+    }
+
+    #[test]
+    fn lda_exc_sparse() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 1e-12, 0.0, 0.3, 0.4, 0.0, 0.0];
+        let dense = func.lda_exc(&rho);
+        let sparse = func.lda_exc_sparse(&rho, 1e-10);
+        assert_eq!(sparse.len(), 2);
+        assert_eq!(sparse[0], (0, dense[0]));
+        assert_eq!(sparse[1], (2, dense[2]));
+    }
+
+    #[test]
+    fn lda_exc_timed() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho: Vec<f64> = (1..=100_000).map(|i| 1e-5 * i as f64).collect();
+        let (exc, elapsed) = func.lda_exc_timed(&rho);
+        assert_eq!(exc, func.lda_exc(&rho));
+        assert!(elapsed.as_nanos() > 0);
+    }
+
+    #[test]
+    fn lda_exc_blocked() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho: Vec<f64> = (1..=20).map(|i| 0.05 * i as f64).collect();
+        let exc = func.lda_exc(&rho);
+        for block in &[1, 2, 3, 7, 10, 64] {
+            assert_eq!(func.lda_exc_blocked(&rho, *block), exc);
+        }
+    }
+
+    #[test]
+    fn lda_exc_stream() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho: Vec<f64> = (1..=12).map(|i| 0.05 * i as f64).collect();
+        let chunks = vec![rho[..2].to_vec(), rho[2..8].to_vec(), rho[8..].to_vec()];
+        let streamed: Vec<f64> = func.lda_exc_stream(chunks.into_iter()).flatten().collect();
+        assert_eq!(streamed, func.lda_exc(&rho));
+    }
+
+    #[test]
+    fn lda_exc_strided() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let dense = [0.1, 0.2, 0.3];
+        let interleaved = [0.1, -1.0, -1.0, 0.2, -1.0, -1.0, 0.3, -1.0, -1.0];
+        assert_eq!(func.lda_exc_strided(&interleaved, 3), func.lda_exc(&dense));
+    }
+
+    #[test]
+    fn lda_exc_strided_polarized() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let dense = [0.1, 0.2, 0.3, 0.4];
+        let interleaved = [0.1, 0.2, -1.0, 0.3, 0.4];
+        assert_eq!(func.lda_exc_strided(&interleaved, 3), func.lda_exc(&dense));
+    }
+
+    #[test]
+    fn lda_vxc() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let vxc = func.lda_vxc(&[0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(vxc.len(), 4);
+    }
+
+    #[test]
+    fn lda_exc_vxc() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let (exc, vxc) = func.lda_exc_vxc(&rho);
+        assert_eq!(exc, func.lda_exc(&rho));
+        assert_eq!(vxc, func.lda_vxc(&rho));
+    }
+
+    #[test]
+    fn lda_vxc_spin() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let vxc = func.lda_vxc(&rho);
+        let spin = func.lda_vxc_spin(&rho);
+        let recombined: Vec<f64> = spin
+            .up
+            .iter()
+            .zip(spin.down.iter())
+            .flat_map(|(up, down)| vec![*up, *down])
+            .collect();
+        assert_eq!(recombined, vxc);
+    }
+
+    #[test]
+    fn lda_fxc() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let fxc = func.lda_fxc(&[0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(fxc.len(), 6);
+    }
+
+    #[test]
+    fn fxc_numeric() {
+        for polarization in &[Polarization::Unpolarized, Polarization::Polarized] {
+            let func = Functional::from_id(ids::LDA_C_PW, *polarization).unwrap();
+            let rho = [0.1, 0.2, 0.3, 0.4];
+            let analytic = func.lda_fxc(&rho);
+            let numeric = func.fxc_numeric(&rho, 1e-5);
+            assert_eq!(numeric.len(), analytic.len());
+            for (n, a) in numeric.iter().zip(analytic.iter()) {
+                assert!((n - a).abs() < 1e-5 * a.abs().max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn fxc_diagonal() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let fxc = func.lda_fxc(&rho);
+        assert_eq!(
+            func.fxc_diagonal(&rho),
+            vec![fxc[0], fxc[2], fxc[3], fxc[5]]
+        );
+        // Slater exchange does not couple the spin channels.
+        assert_eq!(fxc[1], 0.0);
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.fxc_diagonal(&rho), func.lda_fxc(&rho));
+    }
+
+    #[test]
+    fn fxc_dot() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let drho = [0.01, -0.02, 0.03];
+        let fxc = func.lda_fxc(&rho);
+        let expected: Vec<f64> = (0..3).map(|i| fxc[i] * drho[i]).collect();
+        assert_eq!(func.fxc_dot(&rho, &drho), expected);
+    }
+
+    #[test]
+    fn fxc_dot_polarized() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2];
+        let drho = [0.01, -0.02];
+        let f = func.lda_fxc(&rho);
+        let expected = vec![
+            f[0] * drho[0] + f[1] * drho[1],
+            f[1] * drho[0] + f[2] * drho[1],
+        ];
+        assert_eq!(func.fxc_dot(&rho, &drho), expected);
+    }
+
+    #[cfg(feature = "complex")]
+    #[test]
+    fn fxc_dot_complex() {
+        use num_complex::Complex64;
+
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let drho = [
+            Complex64::new(0.01, -0.03),
+            Complex64::new(-0.02, 0.04),
+            Complex64::new(0.05, 0.01),
+            Complex64::new(0.0, -0.02),
+        ];
+        let re: Vec<f64> = drho.iter().map(|d| d.re).collect();
+        let im: Vec<f64> = drho.iter().map(|d| d.im).collect();
+        let expected_re = func.fxc_dot(&rho, &re);
+        let expected_im = func.fxc_dot(&rho, &im);
+        let result = func.fxc_dot_complex(&rho, &drho);
+        for i in 0..4 {
+            assert_eq!(result[i].re, expected_re[i]);
+            assert_eq!(result[i].im, expected_im[i]);
+        }
+    }
+
+    #[test]
+    fn vxc_times_rho() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let vxc = func.lda_vxc(&rho);
+        let expected: Vec<f64> = (0..4).map(|i| vxc[i] * rho[i]).collect();
+        assert_eq!(func.vxc_times_rho(&rho), expected);
+    }
+
+    #[test]
+    fn total_energy() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let weights = [0.5, 0.25, 0.25];
+        let exc = func.lda_exc(&rho);
+        let expected: f64 = (0..3).map(|i| exc[i] * rho[i] * weights[i]).sum();
+        assert!((func.total_energy(&rho, &weights) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scaled_energy() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let weights = [0.5, 0.25, 0.25];
+        let energy = func.total_energy(&rho, &weights);
+        assert!((func.scaled_energy(&rho, 1.0, &weights) - energy).abs() < 1e-12);
+        assert!((func.scaled_energy(&rho, 2.0, &weights) - 2.0 * energy).abs() < 1e-10);
+    }
+
+    #[test]
+    fn virial_energy() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4, 0.5, 0.0];
+        let weights = [0.5, 0.25, 0.25];
+        let energy = func.total_energy(&rho, &weights);
+        assert!((func.virial_energy(&rho, &weights) - energy / 3.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn accumulate_energy() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let weights = [0.5, 0.25, 0.25];
+        let mut acc = 0.0;
+        func.accumulate_energy(&rho[..2], &weights[..1], &mut acc);
+        func.accumulate_energy(&rho[2..], &weights[1..], &mut acc);
+        assert!((acc - func.total_energy(&rho, &weights)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lda_exc_per_spin() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.1, 0.3, 0.3];
+        let weights = [0.5, 0.25];
+        let (up, down) = func.lda_exc_per_spin(&rho, &weights);
+        assert!((up - down).abs() < 1e-14);
+        assert!((up + down - func.total_energy(&rho, &weights)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lda_full() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let weights = [0.5, 0.25];
+        let result = func.lda_full(&rho, &weights);
+        assert_eq!(result.exc_per_particle, func.lda_exc(&rho));
+        assert_eq!(result.vxc, func.lda_vxc(&rho));
+        let mut energy = 0.0;
+        for i in 0..2 {
+            let density = result.exc_per_particle[i] * (rho[2 * i] + rho[2 * i + 1]);
+            assert!((result.exc_density[i] - density).abs() < 1e-14);
+            energy += result.exc_density[i] * weights[i];
+        }
+        assert!((result.integrated_energy - energy).abs() < 1e-14);
+        assert!((result.integrated_energy - func.total_energy(&rho, &weights)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lda_exc_and_energy() {
+        let func = Functional::from_id(1, Polarization::Polarized).unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let weights = [0.5, 0.5];
+        let (exc, energy) = func.lda_exc_and_energy(&rho, &weights);
+        assert_eq!(exc, func.lda_exc(&rho));
+        assert!((energy - func.total_energy(&rho, &weights)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lda_vxc_spin_unpolarized() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        let rho = [0.1, 0.2, 0.3];
+        let spin = func.lda_vxc_spin(&rho);
+        assert_eq!(spin.up, func.lda_vxc(&rho));
+        assert!(spin.down.is_empty());
+    }
+}
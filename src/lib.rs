@@ -3,6 +3,15 @@ extern crate strum_macros;
 #[macro_use]
 extern crate num_derive;
 
+pub mod aligned;
+pub mod builder;
 pub mod error;
+pub mod eval;
+pub mod flags;
 pub mod functional;
+pub mod gga;
+pub mod ids;
+pub mod lda;
+pub mod mgga;
 pub mod util;
+pub mod xc;
@@ -0,0 +1,536 @@
+//! Evaluation of MGGA functionals.
+//!
+//! Densities, laplacians, and kinetic energy densities follow the LibXC layout: one value per
+//! point for unpolarized functionals and interleaved `(up, down)` pairs for polarized
+//! functionals. Contracted gradients `sigma` hold one value per point for unpolarized
+//! functionals and interleaved `(uu, ud, dd)` triples for polarized functionals.
+//!
+//! Functionals which do not need the laplacian or the kinetic energy density accept an empty
+//! `lapl` or `tau`. Otherwise, empty inputs describe zero points and yield empty outputs without
+//! calling into LibXC.
+
+use std::borrow::Cow;
+
+use libxc_sys;
+
+use crate::error::FunctionalError;
+use crate::flags;
+use crate::functional::{Functional, FunctionalFamily, Polarization};
+
+/// Energy per particle and derivatives of an MGGA functional up to third order.
+///
+/// Derivatives which the functional does not provide are `None`. Polarized derivatives hold one
+/// value per point for each unique combination of spin components, in the LibXC order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MggaDerivatives {
+    pub exc: Option<Vec<f64>>,
+    pub vrho: Option<Vec<f64>>,
+    pub vsigma: Option<Vec<f64>>,
+    pub vlapl: Option<Vec<f64>>,
+    pub vtau: Option<Vec<f64>>,
+    pub v2rho2: Option<Vec<f64>>,
+    pub v2rhosigma: Option<Vec<f64>>,
+    pub v2rholapl: Option<Vec<f64>>,
+    pub v2rhotau: Option<Vec<f64>>,
+    pub v2sigma2: Option<Vec<f64>>,
+    pub v2sigmalapl: Option<Vec<f64>>,
+    pub v2sigmatau: Option<Vec<f64>>,
+    pub v2lapl2: Option<Vec<f64>>,
+    pub v2lapltau: Option<Vec<f64>>,
+    pub v2tau2: Option<Vec<f64>>,
+    pub v3rho3: Option<Vec<f64>>,
+    pub v3rho2sigma: Option<Vec<f64>>,
+    pub v3rho2lapl: Option<Vec<f64>>,
+    pub v3rho2tau: Option<Vec<f64>>,
+    pub v3rhosigma2: Option<Vec<f64>>,
+    pub v3rhosigmalapl: Option<Vec<f64>>,
+    pub v3rhosigmatau: Option<Vec<f64>>,
+    pub v3rholapl2: Option<Vec<f64>>,
+    pub v3rholapltau: Option<Vec<f64>>,
+    pub v3rhotau2: Option<Vec<f64>>,
+    pub v3sigma3: Option<Vec<f64>>,
+    pub v3sigma2lapl: Option<Vec<f64>>,
+    pub v3sigma2tau: Option<Vec<f64>>,
+    pub v3sigmalapl2: Option<Vec<f64>>,
+    pub v3sigmalapltau: Option<Vec<f64>>,
+    pub v3sigmatau2: Option<Vec<f64>>,
+    pub v3lapl3: Option<Vec<f64>>,
+    pub v3lapl2tau: Option<Vec<f64>>,
+    pub v3lapltau2: Option<Vec<f64>>,
+    pub v3tau3: Option<Vec<f64>>,
+}
+
+impl Functional {
+    /// Evaluates every derivative of the energy up to third order which the functional provides
+    /// in a single LibXC call.
+    pub fn mgga(&self, rho: &[f64], sigma: &[f64], lapl: &[f64], tau: &[f64]) -> MggaDerivatives {
+        let npoints = self.mgga_npoints(rho, sigma, lapl, tau, flags::HAVE_EXC);
+        let lapl = self.mgga_optional(rho, lapl);
+        let tau = self.mgga_optional(rho, tau);
+        let polarized = self.polarization == Polarization::Polarized;
+        let buffer = |flag: i32, components: usize| {
+            if self.has_flag(flag) {
+                // Unpolarized derivatives hold a single value per point.
+                let components = if polarized { components } else { 1 };
+                Some(vec![0.0; npoints * components])
+            } else {
+                None
+            }
+        };
+        let mut exc = buffer(flags::HAVE_EXC, 1);
+        let mut vrho = buffer(flags::HAVE_VXC, 2);
+        let mut vsigma = buffer(flags::HAVE_VXC, 3);
+        let mut vlapl = buffer(flags::HAVE_VXC, 2);
+        let mut vtau = buffer(flags::HAVE_VXC, 2);
+        let mut v2rho2 = buffer(flags::HAVE_FXC, 3);
+        let mut v2rhosigma = buffer(flags::HAVE_FXC, 6);
+        let mut v2rholapl = buffer(flags::HAVE_FXC, 4);
+        let mut v2rhotau = buffer(flags::HAVE_FXC, 4);
+        let mut v2sigma2 = buffer(flags::HAVE_FXC, 6);
+        let mut v2sigmalapl = buffer(flags::HAVE_FXC, 6);
+        let mut v2sigmatau = buffer(flags::HAVE_FXC, 6);
+        let mut v2lapl2 = buffer(flags::HAVE_FXC, 3);
+        let mut v2lapltau = buffer(flags::HAVE_FXC, 4);
+        let mut v2tau2 = buffer(flags::HAVE_FXC, 3);
+        let mut v3rho3 = buffer(flags::HAVE_KXC, 4);
+        let mut v3rho2sigma = buffer(flags::HAVE_KXC, 9);
+        let mut v3rho2lapl = buffer(flags::HAVE_KXC, 6);
+        let mut v3rho2tau = buffer(flags::HAVE_KXC, 6);
+        let mut v3rhosigma2 = buffer(flags::HAVE_KXC, 12);
+        let mut v3rhosigmalapl = buffer(flags::HAVE_KXC, 12);
+        let mut v3rhosigmatau = buffer(flags::HAVE_KXC, 12);
+        let mut v3rholapl2 = buffer(flags::HAVE_KXC, 6);
+        let mut v3rholapltau = buffer(flags::HAVE_KXC, 8);
+        let mut v3rhotau2 = buffer(flags::HAVE_KXC, 6);
+        let mut v3sigma3 = buffer(flags::HAVE_KXC, 10);
+        let mut v3sigma2lapl = buffer(flags::HAVE_KXC, 12);
+        let mut v3sigma2tau = buffer(flags::HAVE_KXC, 12);
+        let mut v3sigmalapl2 = buffer(flags::HAVE_KXC, 9);
+        let mut v3sigmalapltau = buffer(flags::HAVE_KXC, 12);
+        let mut v3sigmatau2 = buffer(flags::HAVE_KXC, 9);
+        let mut v3lapl3 = buffer(flags::HAVE_KXC, 4);
+        let mut v3lapl2tau = buffer(flags::HAVE_KXC, 6);
+        let mut v3lapltau2 = buffer(flags::HAVE_KXC, 6);
+        let mut v3tau3 = buffer(flags::HAVE_KXC, 4);
+        // LibXC skips the derivatives whose buffer is null.
+        let ptr = |buffer: &mut Option<Vec<f64>>| match buffer {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => std::ptr::null_mut(),
+        };
+        let null = std::ptr::null_mut;
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_mgga(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    lapl.as_ptr(),
+                    tau.as_ptr(),
+                    ptr(&mut exc),
+                    ptr(&mut vrho),
+                    ptr(&mut vsigma),
+                    ptr(&mut vlapl),
+                    ptr(&mut vtau),
+                    ptr(&mut v2rho2),
+                    ptr(&mut v2rhosigma),
+                    ptr(&mut v2rholapl),
+                    ptr(&mut v2rhotau),
+                    ptr(&mut v2sigma2),
+                    ptr(&mut v2sigmalapl),
+                    ptr(&mut v2sigmatau),
+                    ptr(&mut v2lapl2),
+                    ptr(&mut v2lapltau),
+                    ptr(&mut v2tau2),
+                    ptr(&mut v3rho3),
+                    ptr(&mut v3rho2sigma),
+                    ptr(&mut v3rho2lapl),
+                    ptr(&mut v3rho2tau),
+                    ptr(&mut v3rhosigma2),
+                    ptr(&mut v3rhosigmalapl),
+                    ptr(&mut v3rhosigmatau),
+                    ptr(&mut v3rholapl2),
+                    ptr(&mut v3rholapltau),
+                    ptr(&mut v3rhotau2),
+                    ptr(&mut v3sigma3),
+                    ptr(&mut v3sigma2lapl),
+                    ptr(&mut v3sigma2tau),
+                    ptr(&mut v3sigmalapl2),
+                    ptr(&mut v3sigmalapltau),
+                    ptr(&mut v3sigmatau2),
+                    ptr(&mut v3lapl3),
+                    ptr(&mut v3lapl2tau),
+                    ptr(&mut v3lapltau2),
+                    ptr(&mut v3tau3),
+                    // Fourth derivatives are not requested.
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                    null(),
+                )
+            };
+        }
+        MggaDerivatives {
+            exc,
+            vrho,
+            vsigma,
+            vlapl,
+            vtau,
+            v2rho2,
+            v2rhosigma,
+            v2rholapl,
+            v2rhotau,
+            v2sigma2,
+            v2sigmalapl,
+            v2sigmatau,
+            v2lapl2,
+            v2lapltau,
+            v2tau2,
+            v3rho3,
+            v3rho2sigma,
+            v3rho2lapl,
+            v3rho2tau,
+            v3rhosigma2,
+            v3rhosigmalapl,
+            v3rhosigmatau,
+            v3rholapl2,
+            v3rholapltau,
+            v3rhotau2,
+            v3sigma3,
+            v3sigma2lapl,
+            v3sigma2tau,
+            v3sigmalapl2,
+            v3sigmalapltau,
+            v3sigmatau2,
+            v3lapl3,
+            v3lapl2tau,
+            v3lapltau2,
+            v3tau3,
+        }
+    }
+
+    /// Evaluates the exchange-correlation energy per particle at each point.
+    pub fn mgga_exc(&self, rho: &[f64], sigma: &[f64], lapl: &[f64], tau: &[f64]) -> Vec<f64> {
+        let npoints = self.mgga_npoints(rho, sigma, lapl, tau, flags::HAVE_EXC);
+        let lapl = self.mgga_optional(rho, lapl);
+        let tau = self.mgga_optional(rho, tau);
+        let mut zk = vec![0.0; npoints];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_mgga_exc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    lapl.as_ptr(),
+                    tau.as_ptr(),
+                    zk.as_mut_ptr(),
+                )
+            };
+        }
+        zk
+    }
+
+    /// Evaluates the first derivatives of the energy with respect to `rho`, `sigma`, `lapl`,
+    /// and `tau`.
+    pub fn mgga_vxc(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        lapl: &[f64],
+        tau: &[f64],
+    ) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+        let npoints = self.mgga_npoints(rho, sigma, lapl, tau, flags::HAVE_VXC);
+        let lapl = self.mgga_optional(rho, lapl);
+        let tau = self.mgga_optional(rho, tau);
+        let mut vrho = vec![0.0; rho.len()];
+        let mut vsigma = vec![0.0; sigma.len()];
+        let mut vlapl = vec![0.0; rho.len()];
+        let mut vtau = vec![0.0; rho.len()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_mgga_vxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    lapl.as_ptr(),
+                    tau.as_ptr(),
+                    vrho.as_mut_ptr(),
+                    vsigma.as_mut_ptr(),
+                    vlapl.as_mut_ptr(),
+                    vtau.as_mut_ptr(),
+                )
+            };
+        }
+        (vrho, vsigma, vlapl, vtau)
+    }
+
+    /// Evaluates the energy per particle, failing instead of panicking if the functional is
+    /// uninitialized or does not provide the energy through the MGGA interface.
+    pub fn try_mgga_exc(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        lapl: &[f64],
+        tau: &[f64],
+    ) -> Result<Vec<f64>, FunctionalError> {
+        self.check_evaluator(FunctionalFamily::MGGA, flags::HAVE_EXC)?;
+        Ok(self.mgga_exc(rho, sigma, lapl, tau))
+    }
+
+    /// Evaluates the first derivatives of the energy, failing instead of panicking if the
+    /// functional is uninitialized or does not provide them through the MGGA interface.
+    #[allow(clippy::type_complexity)]
+    pub fn try_mgga_vxc(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        lapl: &[f64],
+        tau: &[f64],
+    ) -> Result<(Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>), FunctionalError> {
+        self.check_evaluator(FunctionalFamily::MGGA, flags::HAVE_VXC)?;
+        Ok(self.mgga_vxc(rho, sigma, lapl, tau))
+    }
+
+    /// Evaluates the energy per particle and its first derivatives in a single LibXC call.
+    #[allow(clippy::type_complexity)]
+    pub fn mgga_exc_vxc(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        lapl: &[f64],
+        tau: &[f64],
+    ) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+        // LibXC provides the energy of every functional that provides a potential.
+        let npoints = self.mgga_npoints(rho, sigma, lapl, tau, flags::HAVE_VXC);
+        let lapl = self.mgga_optional(rho, lapl);
+        let tau = self.mgga_optional(rho, tau);
+        let mut zk = vec![0.0; npoints];
+        let mut vrho = vec![0.0; rho.len()];
+        let mut vsigma = vec![0.0; sigma.len()];
+        let mut vlapl = vec![0.0; rho.len()];
+        let mut vtau = vec![0.0; rho.len()];
+        if npoints > 0 {
+            unsafe {
+                libxc_sys::xc_mgga_exc_vxc(
+                    self.xc_func,
+                    npoints as _,
+                    rho.as_ptr(),
+                    sigma.as_ptr(),
+                    lapl.as_ptr(),
+                    tau.as_ptr(),
+                    zk.as_mut_ptr(),
+                    vrho.as_mut_ptr(),
+                    vsigma.as_mut_ptr(),
+                    vlapl.as_mut_ptr(),
+                    vtau.as_mut_ptr(),
+                )
+            };
+        }
+        (zk, vrho, vsigma, vlapl, vtau)
+    }
+
+    // Returns the laplacian or kinetic energy density handed to LibXC, substituting zeros for an
+    // empty slice.
+    fn mgga_optional<'a>(&self, rho: &[f64], values: &'a [f64]) -> Cow<'a, [f64]> {
+        if values.is_empty() {
+            Cow::Owned(vec![0.0; rho.len()])
+        } else {
+            Cow::Borrowed(values)
+        }
+    }
+
+    // Validates the input of an MGGA evaluation and returns the number of points.
+    fn mgga_npoints(
+        &self,
+        rho: &[f64],
+        sigma: &[f64],
+        lapl: &[f64],
+        tau: &[f64],
+        flag: i32,
+    ) -> usize {
+        if let Err(err) = self.check_evaluator(FunctionalFamily::MGGA, flag) {
+            panic!("{}", err);
+        }
+        let nspin = self.n_spin();
+        assert_eq!(
+            rho.len() % nspin,
+            0,
+            "density length must be a multiple of the number of spin channels"
+        );
+        let npoints = rho.len() / nspin;
+        assert_eq!(
+            sigma.len(),
+            npoints * (2 * nspin - 1),
+            "sigma length does not match the number of points"
+        );
+        if self.needs_laplacian() || !lapl.is_empty() {
+            assert_eq!(
+                lapl.len(),
+                rho.len(),
+                "laplacian must have the layout of the density"
+            );
+        }
+        if self.needs_tau() || !tau.is_empty() {
+            assert_eq!(
+                tau.len(),
+                rho.len(),
+                "kinetic energy density must have the layout of the density"
+            );
+        }
+        npoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flags;
+    use crate::functional::{Functional, Polarization};
+    use crate::ids;
+
+    #[test]
+    fn mgga() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let (rho, sigma, lapl, tau) = ([0.1, 0.2], [0.01, 0.02], [0.0, 0.0], [0.05, 0.1]);
+        let derivatives = func.mgga(&rho, &sigma, &lapl, &tau);
+        let (exc, vrho, vsigma, vlapl, vtau) = func.mgga_exc_vxc(&rho, &sigma, &lapl, &tau);
+        assert_eq!(derivatives.exc, Some(exc));
+        assert_eq!(derivatives.vrho, Some(vrho));
+        assert_eq!(derivatives.vsigma, Some(vsigma));
+        assert_eq!(derivatives.vlapl, Some(vlapl));
+        assert_eq!(derivatives.vtau, Some(vtau));
+        if func.has_flag(flags::HAVE_FXC) {
+            assert_eq!(derivatives.v2tau2.unwrap().len(), rho.len());
+        } else {
+            assert_eq!(derivatives.v2tau2, None);
+        }
+        if !func.has_flag(flags::HAVE_KXC) {
+            assert_eq!(derivatives.v3tau3, None);
+        }
+    }
+
+    #[test]
+    fn mgga_exc() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let exc = func.mgga_exc(&[0.1, 0.2], &[0.01, 0.02], &[0.0, 0.0], &[0.05, 0.1]);
+        assert_eq!(exc.len(), 2);
+        assert!(exc.iter().all(|e| *e < 0.0));
+    }
+
+    #[test]
+    fn mgga_empty() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        assert!(func.mgga_exc(&[], &[], &[], &[]).is_empty());
+    }
+
+    #[test]
+    fn mgga_vxc() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Polarized).unwrap();
+        let (vrho, vsigma, vlapl, vtau) =
+            func.mgga_vxc(&[0.1, 0.2], &[0.01, 0.005, 0.02], &[0.0, 0.0], &[0.05, 0.1]);
+        assert_eq!(vrho.len(), 2);
+        assert_eq!(vsigma.len(), 3);
+        assert_eq!(vlapl.len(), 2);
+        assert_eq!(vtau.len(), 2);
+    }
+
+    #[test]
+    fn try_mgga_exc() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let (rho, sigma, lapl, tau) = ([0.1], [0.01], [0.0], [0.05]);
+        assert_eq!(
+            func.try_mgga_exc(&rho, &sigma, &lapl, &tau).unwrap(),
+            func.mgga_exc(&rho, &sigma, &lapl, &tau)
+        );
+        let pbe = Functional::from_id(ids::GGA_X_PBE, Polarization::Unpolarized).unwrap();
+        assert!(pbe.try_mgga_vxc(&rho, &sigma, &lapl, &tau).is_err());
+    }
+
+    #[test]
+    fn mgga_exc_vxc() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        let (rho, sigma, lapl, tau) = ([0.1, 0.2], [0.01, 0.02], [0.0, 0.0], [0.05, 0.1]);
+        let (exc, vrho, vsigma, vlapl, vtau) = func.mgga_exc_vxc(&rho, &sigma, &lapl, &tau);
+        assert_eq!(exc, func.mgga_exc(&rho, &sigma, &lapl, &tau));
+        assert_eq!(
+            (vrho, vsigma, vlapl, vtau),
+            func.mgga_vxc(&rho, &sigma, &lapl, &tau)
+        );
+    }
+
+    #[test]
+    fn mgga_exc_without_tau() {
+        let func = Functional::from_id(ids::MGGA_X_SCANL, Polarization::Unpolarized).unwrap();
+        let exc = func.mgga_exc(&[0.1, 0.2], &[0.01, 0.02], &[0.1, -0.1], &[]);
+        assert_eq!(exc.len(), 2);
+        assert!(exc.iter().all(|e| e.is_finite() && *e < 0.0));
+    }
+
+    #[test]
+    fn mgga_input_layout() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Polarized).unwrap();
+        let layout = func.input_layout();
+        let npoints = 3;
+        let rho = vec![0.1; npoints * layout.rho];
+        let sigma = vec![0.01; npoints * layout.sigma];
+        let lapl = vec![0.0; npoints * layout.lapl];
+        let tau = vec![0.05; npoints * layout.tau];
+        assert!(lapl.is_empty());
+        let exc = func.mgga_exc(&rho, &sigma, &lapl, &tau);
+        assert_eq!(exc.len(), npoints);
+        assert_eq!(
+            exc,
+            func.mgga_exc(&rho, &sigma, &vec![0.0; rho.len()], &tau)
+        );
+        let (vrho, vsigma, _, vtau) = func.mgga_vxc(&rho, &sigma, &lapl, &tau);
+        assert_eq!(vrho.len(), rho.len());
+        assert_eq!(vsigma.len(), sigma.len());
+        assert_eq!(vtau.len(), tau.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn mgga_exc_missing_lapl() {
+        let func = Functional::from_id(ids::MGGA_X_SCANL, Polarization::Unpolarized).unwrap();
+        func.mgga_exc(&[0.1], &[0.01], &[], &[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mgga_exc_missing_tau() {
+        let func = Functional::from_id(ids::MGGA_X_SCAN, Polarization::Unpolarized).unwrap();
+        func.mgga_exc(&[0.1], &[0.01], &[0.0], &[]);
+    }
+}
@@ -1,52 +1,98 @@
 // TODO: xc_family_from_id
 
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
-use std::mem::forget;
 
 use libc::c_char;
 use libxc_sys;
 
 use crate::error::FunctionalError;
+use crate::functional::{
+    Functional, FunctionalFamily, FunctionalInfo, FunctionalKind, Polarization,
+};
+use crate::ids;
 
-pub(crate) fn _rust_string_from_c_buf(c_buf: *const c_char) -> String {
+/// Number of electronvolts in one Hartree (CODATA 2018).
+pub const HARTREE_TO_EV: f64 = 27.211386245988;
+
+/// Converts an energy from Hartree, the unit of every LibXC energy, to electronvolts.
+pub fn hartree_to_ev(energy: f64) -> f64 {
+    energy * HARTREE_TO_EV
+}
+
+pub(crate) fn _rust_string_from_c_buf(c_buf: *const c_char) -> Result<String, FunctionalError> {
+    if c_buf.is_null() {
+        return Err(FunctionalError::InvalidString);
+    }
+    let c_str: &CStr = unsafe { CStr::from_ptr(c_buf) };
+    match c_str.to_str() {
+        Ok(str_slice) => Ok(str_slice.to_owned()),
+        Err(_) => Err(FunctionalError::InvalidString),
+    }
+}
+
+// Replaces invalid UTF-8 with U+FFFD instead of failing.
+pub(crate) fn _rust_string_from_c_buf_lossy(
+    c_buf: *const c_char,
+) -> Result<String, FunctionalError> {
+    if c_buf.is_null() {
+        return Err(FunctionalError::InvalidString);
+    }
     let c_str: &CStr = unsafe { CStr::from_ptr(c_buf) };
-    let str_slice: &str = c_str.to_str().unwrap();
-    str_slice.to_owned()
+    Ok(c_str.to_string_lossy().into_owned())
 }
 
 /// Returns the current LibXC version as a semantic versioning tuple.
-pub fn version() -> (i32, i32, i32) {
+pub fn version() -> Result<(i32, i32, i32), FunctionalError> {
     let mut major: i32 = -1;
     let mut minor: i32 = -1;
     let mut micro: i32 = -1;
     unsafe { libxc_sys::xc_version(&mut major, &mut minor, &mut micro) };
-    (major, minor, micro)
+    // A negative component means LibXC did not fill it in.
+    if major < 0 || minor < 0 || micro < 0 {
+        Err(FunctionalError::InvalidVersion)
+    } else {
+        Ok((major, minor, micro))
+    }
 }
 
 /// Returns the current LibXC version as a string.
-pub fn version_string() -> String {
+pub fn version_string() -> Result<String, FunctionalError> {
     let c_buf: *const c_char = unsafe { libxc_sys::xc_version_string() };
-    _rust_string_from_c_buf(c_buf)
+    _rust_string_from_c_buf_lossy(c_buf)
 }
 
 /// Returns the reference for the current LibXC version as a string.
-pub fn reference() -> String {
+pub fn reference() -> Result<String, FunctionalError> {
     let c_buf: *const c_char = unsafe { libxc_sys::xc_reference() };
-    _rust_string_from_c_buf(c_buf)
+    _rust_string_from_c_buf_lossy(c_buf)
 }
 
 /// Returns the doi of the reference for the current LibXC version as a string.
-pub fn reference_doi() -> String {
+pub fn reference_doi() -> Result<String, FunctionalError> {
     let c_buf: *const c_char = unsafe { libxc_sys::xc_reference_doi() };
     _rust_string_from_c_buf(c_buf)
 }
 
+/// Returns the version and reference of the current LibXC as a block of text suitable for logging.
+pub fn build_info() -> Result<String, FunctionalError> {
+    Ok(format!(
+        "LibXC version: {}\nReference: {}\nDOI: {}",
+        version_string()?,
+        reference()?,
+        reference_doi()?
+    ))
+}
+
 /// Returns the functional ID for a given name.
 pub fn functional_number<'a, S>(name: S) -> Result<i32, FunctionalError>
 where
     S: Into<&'a str>,
 {
-    let c_string = CString::new(name.into()).unwrap();
+    let c_string = match CString::new(name.into()) {
+        Ok(c_string) => c_string,
+        Err(_) => return Err(FunctionalError::InvalidName),
+    };
     let c_ptr: *const c_char = c_string.as_ptr();
     let number = unsafe { libxc_sys::xc_functional_get_number(c_ptr) };
     if number < 0 {
@@ -56,17 +102,68 @@ where
     }
 }
 
+// Informal names of popular functionals mapped to their LibXC ids.
+const FUNCTIONAL_ALIASES: &[(&str, i32)] = &[
+    ("SLATER", ids::LDA_X),
+    ("VWN", ids::LDA_C_VWN),
+    ("PZ81", ids::LDA_C_PZ),
+    ("PW92", ids::LDA_C_PW),
+    ("PBE", ids::GGA_X_PBE),
+    ("B88", ids::GGA_X_B88),
+    ("PW91", ids::GGA_X_PW91),
+    ("PBESOL", ids::GGA_X_PBE_SOL),
+    ("RPBE", ids::GGA_X_RPBE),
+    ("LYP", ids::GGA_C_LYP),
+    ("TPSS", ids::MGGA_X_TPSS),
+    ("SCAN", ids::MGGA_X_SCAN),
+    ("B3PW91", ids::HYB_GGA_XC_B3PW91),
+    ("B3LYP", ids::HYB_GGA_XC_B3LYP),
+    ("PBE0", ids::HYB_GGA_XC_PBEH),
+    ("HSE03", ids::HYB_GGA_XC_HSE03),
+    ("HSE06", ids::HYB_GGA_XC_HSE06),
+    ("CAM-B3LYP", ids::HYB_GGA_XC_CAM_B3LYP),
+    ("TPSSH", ids::HYB_MGGA_XC_TPSSH),
+];
+
+/// Returns the functional ID for a common informal name such as "B3LYP" or "PBE0".
+///
+/// Aliases are matched case-insensitively. Anything not found in the alias table is looked up as a
+/// regular LibXC name.
+pub fn functional_id_from_alias(alias: &str) -> Option<i32> {
+    FUNCTIONAL_ALIASES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(alias))
+        .map(|(_, number)| *number)
+        .or_else(|| functional_number(alias).ok())
+}
+
 /// Returns the functional name for a given id.
 pub fn functional_name(number: i32) -> Result<String, FunctionalError> {
     let numbers = available_functional_numbers();
     if numbers.contains(&number) {
         let c_buf = unsafe { libxc_sys::xc_functional_get_name(number) };
-        Ok(_rust_string_from_c_buf(c_buf))
+        _rust_string_from_c_buf(c_buf)
     } else {
         Err(FunctionalError::InvalidID)
     }
 }
 
+/// Returns the ID and registry name of every available functional whose name contains
+/// `substring`, ignoring case.
+pub fn search_functionals(substring: &str) -> Vec<(i32, String)> {
+    let needle = substring.to_lowercase();
+    available_functional_numbers()
+        .into_iter()
+        .filter_map(|number| {
+            let c_buf = unsafe { libxc_sys::xc_functional_get_name(number) };
+            _rust_string_from_c_buf(c_buf)
+                .ok()
+                .map(|name| (number, name))
+        })
+        .filter(|(_, name)| name.to_lowercase().contains(&needle))
+        .collect()
+}
+
 /// Returns the total number of available functionals.
 pub fn number_of_functionals() -> i32 {
     unsafe { libxc_sys::xc_number_of_functionals() }
@@ -74,55 +171,298 @@ pub fn number_of_functionals() -> i32 {
 
 /// Returns a vec of all available functional IDs.
 pub fn available_functional_numbers() -> Vec<i32> {
-    let n_funcs = number_of_functionals() as usize;
-    let length = n_funcs - 1;
-    let mut vec: Vec<i32> = Vec::with_capacity(length);
-    let ptr = vec.as_mut_ptr();
-    forget(vec);
-    unsafe { libxc_sys::xc_available_functional_numbers(ptr) };
-    unsafe { Vec::from_raw_parts(ptr, length, length) }
+    // LibXC writes one entry per functional.
+    let n_funcs = listing_len(number_of_functionals());
+    if n_funcs == 0 {
+        return Vec::new();
+    }
+    let mut vec: Vec<i32> = vec![0; n_funcs];
+    unsafe { libxc_sys::xc_available_functional_numbers(vec.as_mut_ptr()) };
+    vec
+}
+
+// Converts a functional count reported by LibXC into a buffer length, treating a negative count
+// from a degenerate build as empty instead of wrapping around.
+fn listing_len(count: i32) -> usize {
+    count.max(0) as usize
 }
 
 /// Returns a vec of all available functional names.
-pub fn available_functional_names() -> Vec<String> {
+pub fn available_functional_names() -> Result<Vec<String>, FunctionalError> {
     // Getting a vec of strings through the C FFI is too complicated for me.
     // This should actually be done with an FFI call to `xc_available_functional_names`.
     available_functional_numbers()
         .iter()
-        .map(|number| functional_name(*number).unwrap())
+        .map(|number| functional_name(*number))
         .collect()
 }
 
+/// Returns the metadata of the functional with a given id.
+pub fn info_only(number: i32) -> Result<FunctionalInfo, FunctionalError> {
+    Functional::from_id(number, Polarization::Unpolarized)?.info()
+}
+
+/// Returns an iterator over the metadata of every available functional.
+///
+/// Each functional is released as soon as its metadata has been read, so no LibXC handles are
+/// kept alive while iterating. Functionals which fail to initialize are skipped.
+pub fn iter_functional_info() -> impl Iterator<Item = FunctionalInfo> {
+    available_functional_numbers()
+        .into_iter()
+        .filter_map(|number| info_only(number).ok())
+}
+
+/// Returns every available functional which is marked stable and not under development.
+pub fn stable_functionals(polarization: Polarization) -> Vec<Functional> {
+    available_functional_numbers()
+        .into_iter()
+        .filter_map(|number| Functional::from_id(number, polarization).ok())
+        .filter(|func| func.is_stable() && !func.is_development())
+        .collect()
+}
+
+/// Returns every available functional which provides both exchange and correlation on its own.
+pub fn complete_xc_functionals(polarization: Polarization) -> Vec<Functional> {
+    iter_functional_info()
+        .filter(|info| info.kind == FunctionalKind::ExchangeCorrelation)
+        .filter_map(|info| info.into_functional(polarization).ok())
+        .collect()
+}
+
+/// Returns a canonical functional of the given family and kind, such as PBE for GGA exchange.
+pub fn default_functional(
+    family: FunctionalFamily,
+    kind: FunctionalKind,
+    polarization: Polarization,
+) -> Option<Functional> {
+    let number = match (family, kind) {
+        (FunctionalFamily::LDA, FunctionalKind::Exchange) => ids::LDA_X,
+        (FunctionalFamily::LDA, FunctionalKind::Correlation) => ids::LDA_C_PW,
+        (FunctionalFamily::LDA, FunctionalKind::Kinetic) => ids::LDA_K_TF,
+        (FunctionalFamily::GGA, FunctionalKind::Exchange) => ids::GGA_X_PBE,
+        (FunctionalFamily::GGA, FunctionalKind::Correlation) => ids::GGA_C_PBE,
+        (FunctionalFamily::MGGA, FunctionalKind::Exchange) => ids::MGGA_X_SCAN,
+        (FunctionalFamily::MGGA, FunctionalKind::Correlation) => ids::MGGA_C_SCAN,
+        (FunctionalFamily::HybridGGA, FunctionalKind::ExchangeCorrelation) => ids::HYB_GGA_XC_B3LYP,
+        (FunctionalFamily::HybridMGGA, FunctionalKind::ExchangeCorrelation) => {
+            ids::HYB_MGGA_XC_TPSSH
+        }
+        _ => return None,
+    };
+    Functional::from_id(number, polarization).ok()
+}
+
+/// Returns a short human-readable description of a functional family.
+pub fn family_description(family: FunctionalFamily) -> &'static str {
+    match family {
+        FunctionalFamily::Unknown => "Unknown family",
+        FunctionalFamily::LDA => "Local Density Approximation",
+        FunctionalFamily::GGA => "Generalized Gradient Approximation",
+        FunctionalFamily::MGGA => "Meta-Generalized Gradient Approximation",
+        FunctionalFamily::LCA => "Local Current Approximation",
+        FunctionalFamily::OEP => "Optimized Effective Potential",
+        FunctionalFamily::HybridGGA => "Hybrid Generalized Gradient Approximation",
+        FunctionalFamily::HybridMGGA => "Hybrid Meta-Generalized Gradient Approximation",
+        FunctionalFamily::HybridLDA => "Hybrid Local Density Approximation",
+    }
+}
+
+/// Returns the number of available functionals of each family.
+pub fn functional_counts_by_family() -> HashMap<FunctionalFamily, usize> {
+    let mut counts: HashMap<FunctionalFamily, usize> = HashMap::new();
+    for info in iter_functional_info() {
+        *counts.entry(info.family).or_default() += 1;
+    }
+    counts
+}
+
+/// Returns every available functional grouped by family.
+///
+/// Functionals which fail to initialize are skipped.
+pub fn functionals_by_family(
+    polarization: Polarization,
+) -> HashMap<FunctionalFamily, Vec<Functional>> {
+    let mut map: HashMap<FunctionalFamily, Vec<Functional>> = HashMap::new();
+    for number in available_functional_numbers() {
+        let func = match Functional::from_id(number, polarization) {
+            Ok(func) => func,
+            Err(_) => continue,
+        };
+        if let Ok(family) = func.family() {
+            map.entry(family).or_default().push(func);
+        }
+    }
+    map
+}
+
+/// Evaluates an LDA functional over `n` log-spaced Wigner-Seitz radii between `rs_min` and `rs_max`.
+///
+/// Returns `(rs, exc)` pairs for the uniform unpolarized density of each radius.
+pub fn density_sweep(func: &Functional, rs_min: f64, rs_max: f64, n: usize) -> Vec<(f64, f64)> {
+    let rs: Vec<f64> = (0..n)
+        .map(|i| match n {
+            1 => rs_min,
+            _ => rs_min * (rs_max / rs_min).powf(i as f64 / (n - 1) as f64),
+        })
+        .collect();
+    let nspin = func.n_spin();
+    let rho: Vec<f64> = rs
+        .iter()
+        .flat_map(|r| {
+            let density = 3.0 / (4.0 * std::f64::consts::PI * r.powi(3));
+            vec![density / nspin as f64; nspin]
+        })
+        .collect();
+    rs.into_iter().zip(func.lda_exc(&rho)).collect()
+}
+
+/// Evaluates the energy per particle of the uniform electron gas with Wigner-Seitz radius `rs`
+/// and spin polarization `zeta` using the polarized LDA functional `functional_id`.
+pub fn uniform_gas_correlation_spin(
+    rs: f64,
+    zeta: f64,
+    functional_id: i32,
+) -> Result<f64, FunctionalError> {
+    let func = Functional::from_id(functional_id, Polarization::Polarized)?;
+    let density = 3.0 / (4.0 * std::f64::consts::PI * rs.powi(3));
+    let rho = spin_densities_from_zeta(&[density], &[zeta]);
+    Ok(func.lda_exc(&rho)[0])
+}
+
+/// Evaluates the energy per particle of the LDA functional `id` both unpolarized on `rho_unpol`
+/// and polarized on the interleaved `rho_pol`.
+pub fn evaluate_both_polarizations(
+    id: i32,
+    rho_unpol: &[f64],
+    rho_pol: &[f64],
+) -> Result<(Vec<f64>, Vec<f64>), FunctionalError> {
+    let evaluate = |polarization: Polarization, rho: &[f64]| {
+        Functional::from_id(id, polarization).map(|func| func.lda_exc(rho))
+    };
+    Ok((
+        evaluate(Polarization::Unpolarized, rho_unpol)?,
+        evaluate(Polarization::Polarized, rho_pol)?,
+    ))
+}
+
+/// Returns the total density at each point, summing the `(up, down)` pairs of polarized densities.
+pub fn total_density(rho: &[f64], polarization: Polarization) -> Vec<f64> {
+    match polarization {
+        Polarization::Unpolarized => rho.to_vec(),
+        Polarization::Polarized => rho.chunks(2).map(|n| n.iter().sum()).collect(),
+    }
+}
+
+/// Returns interleaved `(up, down)` densities from total densities `n` and spin polarizations
+/// `zeta`, using `up = n (1 + zeta) / 2` and `down = n (1 - zeta) / 2`.
+pub fn spin_densities_from_zeta(n: &[f64], zeta: &[f64]) -> Vec<f64> {
+    assert_eq!(
+        n.len(),
+        zeta.len(),
+        "one spin polarization is required per point"
+    );
+    n.iter()
+        .zip(zeta.iter())
+        .flat_map(|(n, z)| vec![0.5 * n * (1.0 + z), 0.5 * n * (1.0 - z)])
+        .collect()
+}
+
+/// Returns the contracted gradient `sigma = |grad rho|^2` for each point of an unpolarized density.
+pub fn sigma_from_gradient(grad: &[[f64; 3]]) -> Vec<f64> {
+    grad.iter().map(|g| dot(g, g)).collect()
+}
+
+/// Returns the contracted gradients of a polarized density as interleaved (uu, ud, dd) triples.
+pub fn sigma_from_gradient_polarized(grad_up: &[[f64; 3]], grad_down: &[[f64; 3]]) -> Vec<f64> {
+    assert_eq!(
+        grad_up.len(),
+        grad_down.len(),
+        "spin channels must have the same number of points"
+    );
+    grad_up
+        .iter()
+        .zip(grad_down.iter())
+        .flat_map(|(up, down)| vec![dot(up, up), dot(up, down), dot(down, down)])
+        .collect()
+}
+
+/// Returns the contracted gradient `sigma` corresponding to a reduced gradient `s` at density `rho`.
+pub fn sigma_from_s(rho: f64, s: f64) -> f64 {
+    (s * reduced_gradient_scale(rho)).powi(2)
+}
+
+/// Returns the reduced gradient `s = |grad rho| / (2 (3 pi^2)^(1/3) rho^(4/3))` at density `rho`.
+pub fn s_from_sigma(rho: f64, sigma: f64) -> f64 {
+    sigma.sqrt() / reduced_gradient_scale(rho)
+}
+
+fn reduced_gradient_scale(rho: f64) -> f64 {
+    2.0 * (3.0 * std::f64::consts::PI.powi(2)).cbrt() * rho.powf(4.0 / 3.0)
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::error::FunctionalError;
+    use crate::functional::{Functional, FunctionalFamily, FunctionalKind, Polarization};
+    use crate::ids;
     use crate::util;
 
     #[test]
     fn version() {
-        let (major, minor, micro) = util::version();
+        let (major, minor, micro) = util::version().unwrap();
         assert!(major >= 0);
         assert!(minor >= 0);
         assert!(micro >= 0);
     }
 
+    #[test]
+    fn rust_string_from_c_buf_invalid_utf8() {
+        let buf = b"\xc3\x28\0";
+        let c_buf = buf.as_ptr() as *const libc::c_char;
+        match util::_rust_string_from_c_buf(c_buf) {
+            Err(FunctionalError::InvalidString) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn rust_string_from_c_buf_lossy() {
+        let buf = b"Slater\xff exchange\0";
+        let c_buf = buf.as_ptr() as *const libc::c_char;
+        assert!(util::_rust_string_from_c_buf(c_buf).is_err());
+        let lossy = util::_rust_string_from_c_buf_lossy(c_buf).unwrap();
+        assert_eq!(lossy, "Slater\u{fffd} exchange");
+        assert!(util::_rust_string_from_c_buf_lossy(std::ptr::null()).is_err());
+    }
+
     #[test]
     fn version_string() {
-        let result = util::version_string();
+        let result = util::version_string().unwrap();
         assert!(result.len() > 0);
     }
 
     #[test]
     fn reference() {
-        let result = util::reference();
+        let result = util::reference().unwrap();
         assert!(result.len() > 0);
     }
 
     #[test]
     fn reference_doi() {
-        let result = util::reference_doi();
+        let result = util::reference_doi().unwrap();
         assert!(result.len() > 0);
     }
 
+    #[test]
+    fn build_info() {
+        let info = util::build_info().unwrap();
+        assert!(info.contains(&util::version_string().unwrap()));
+    }
+
     #[test]
     fn functional_number_valid() {
         let name = "XC_GGA_X_GAM";
@@ -141,6 +481,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn functional_number_interior_nul() {
+        let name = "XC_GGA\0_X_GAM";
+        assert!(util::functional_number(name).is_err());
+    }
+
+    #[test]
+    fn functional_id_from_alias() {
+        assert_eq!(util::functional_id_from_alias("B3LYP"), Some(402));
+        assert_eq!(util::functional_id_from_alias("pbe0"), Some(406));
+        assert_eq!(util::functional_id_from_alias("XC_GGA_X_GAM"), Some(32));
+        assert_eq!(util::functional_id_from_alias("INVALID_NAME"), None);
+    }
+
     #[test]
     fn functional_name_valid() {
         let number = 32;
@@ -162,16 +516,198 @@ mod tests {
     #[test]
     fn available_functional_numbers() {
         let n_funcs = util::number_of_functionals() as usize;
-        let length = n_funcs - 1;
         let numbers = util::available_functional_numbers();
-        assert_eq!(numbers.len(), length);
+        assert_eq!(numbers.len(), n_funcs);
+    }
+
+    #[test]
+    fn listing_len() {
+        assert_eq!(super::listing_len(0), 0);
+        assert_eq!(super::listing_len(-1), 0);
+        assert_eq!(super::listing_len(5), 5);
     }
 
     #[test]
     fn available_functional_names() {
         let n_funcs = util::number_of_functionals() as usize;
-        let length = n_funcs - 1;
-        let names = util::available_functional_names();
-        assert_eq!(names.len(), length);
+        let names = util::available_functional_names().unwrap();
+        assert_eq!(names.len(), n_funcs);
+    }
+
+    #[test]
+    fn info_only() {
+        let info = util::info_only(1).unwrap();
+        let func = info.into_functional(Polarization::Unpolarized).unwrap();
+        let exc = func.lda_exc(&[0.1, 0.2]);
+        assert_eq!(exc.len(), 2);
+    }
+
+    #[test]
+    fn iter_functional_info() {
+        let count = util::iter_functional_info().count();
+        assert_eq!(count, util::number_of_functionals() as usize);
+    }
+
+    #[test]
+    fn stable_functionals() {
+        let funcs = util::stable_functionals(Polarization::Unpolarized);
+        for func in funcs {
+            assert!(func.is_stable());
+            assert!(!func.is_development());
+        }
+    }
+
+    #[test]
+    fn complete_xc_functionals() {
+        let funcs = util::complete_xc_functionals(Polarization::Unpolarized);
+        assert!(!funcs.is_empty());
+        assert!(funcs.iter().all(|func| func.is_complete_xc()));
+        assert!(funcs
+            .iter()
+            .any(|func| func.number() == ids::HYB_GGA_XC_B3LYP));
+    }
+
+    #[test]
+    fn default_functional() {
+        let func = util::default_functional(
+            FunctionalFamily::GGA,
+            FunctionalKind::Exchange,
+            Polarization::Unpolarized,
+        )
+        .unwrap();
+        assert_eq!(func.family().unwrap(), FunctionalFamily::GGA);
+        assert_eq!(func.kind().unwrap(), FunctionalKind::Exchange);
+        let func = util::default_functional(
+            FunctionalFamily::OEP,
+            FunctionalKind::Exchange,
+            Polarization::Unpolarized,
+        );
+        assert!(func.is_none());
+    }
+
+    #[test]
+    fn hartree_to_ev() {
+        let func = Functional::from_id(1, Polarization::Unpolarized).unwrap();
+        assert_eq!(func.energy_units(), "Hartree");
+        let exc = func.lda_exc(&[0.3])[0];
+        assert!((util::hartree_to_ev(exc) - 27.211386245988 * exc).abs() < 1e-12);
+    }
+
+    #[test]
+    fn search_functionals() {
+        let results = util::search_functionals("PbE");
+        assert!(results.iter().any(|(id, _)| *id == ids::GGA_X_PBE));
+        assert!(results
+            .iter()
+            .all(|(_, name)| name.to_lowercase().contains("pbe")));
+    }
+
+    #[test]
+    fn family_description() {
+        let families = [
+            FunctionalFamily::Unknown,
+            FunctionalFamily::LDA,
+            FunctionalFamily::GGA,
+            FunctionalFamily::MGGA,
+            FunctionalFamily::LCA,
+            FunctionalFamily::OEP,
+            FunctionalFamily::HybridGGA,
+            FunctionalFamily::HybridMGGA,
+            FunctionalFamily::HybridLDA,
+        ];
+        for family in &families {
+            assert!(!util::family_description(*family).is_empty());
+        }
+        assert_eq!(
+            util::family_description(FunctionalFamily::LDA),
+            "Local Density Approximation"
+        );
+    }
+
+    #[test]
+    fn functional_counts_by_family() {
+        let counts = util::functional_counts_by_family();
+        let total: usize = counts.values().sum();
+        assert_eq!(total, util::number_of_functionals() as usize);
+        assert!(counts[&FunctionalFamily::GGA] > 0);
+    }
+
+    #[test]
+    fn functionals_by_family() {
+        let map = util::functionals_by_family(Polarization::Unpolarized);
+        let lda = &map[&FunctionalFamily::LDA];
+        assert!(!lda.is_empty());
+        assert!(lda
+            .iter()
+            .all(|func| func.family().unwrap() == FunctionalFamily::LDA));
+    }
+
+    #[test]
+    fn density_sweep() {
+        let func = Functional::from_id(12, Polarization::Unpolarized).unwrap();
+        let sweep = util::density_sweep(&func, 0.1, 10.0, 25);
+        assert_eq!(sweep.len(), 25);
+        assert!((sweep[0].0 - 0.1).abs() < 1e-12);
+        assert!((sweep[24].0 - 10.0).abs() < 1e-12);
+        assert!(sweep.windows(2).all(|w| w[0].1 < w[1].1));
+    }
+
+    #[test]
+    fn sigma_from_s() {
+        let rho = 0.3;
+        let s = 1.25;
+        let sigma = util::sigma_from_s(rho, s);
+        assert!((util::s_from_sigma(rho, sigma) - s).abs() < 1e-12);
+    }
+
+    #[test]
+    fn uniform_gas_correlation_spin() {
+        // Fully polarized PW92 correlation at rs = 1 from the published parameters.
+        let ec = util::uniform_gas_correlation_spin(1.0, 1.0, ids::LDA_C_PW).unwrap();
+        assert!((ec - -0.0315925).abs() < 1e-6);
+        let unpolarized = util::uniform_gas_correlation_spin(1.0, 0.0, ids::LDA_C_PW).unwrap();
+        assert!(unpolarized < ec);
+        assert!(util::uniform_gas_correlation_spin(1.0, 0.0, -1).is_err());
+    }
+
+    #[test]
+    fn evaluate_both_polarizations() {
+        let rho_unpol = [0.1, 0.2, 0.3];
+        let rho_pol = util::spin_densities_from_zeta(&rho_unpol, &[0.0; 3]);
+        let (unpol, pol) =
+            util::evaluate_both_polarizations(ids::LDA_C_PW, &rho_unpol, &rho_pol).unwrap();
+        for (u, p) in unpol.iter().zip(pol.iter()) {
+            assert!((u - p).abs() < 1e-10);
+        }
+        assert!(util::evaluate_both_polarizations(-1, &rho_unpol, &rho_pol).is_err());
+    }
+
+    #[test]
+    fn total_density() {
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        assert_eq!(util::total_density(&rho, Polarization::Unpolarized), rho);
+        let total = util::total_density(&rho, Polarization::Polarized);
+        assert_eq!(total, vec![0.1 + 0.2, 0.3 + 0.4]);
+    }
+
+    #[test]
+    fn spin_densities_from_zeta() {
+        let rho = util::spin_densities_from_zeta(&[0.4, 0.6], &[0.0, 1.0]);
+        assert_eq!(rho, vec![0.2, 0.2, 0.6, 0.0]);
+    }
+
+    #[test]
+    fn sigma_from_gradient() {
+        let grad = [[1.0, 2.0, 3.0], [0.5, -0.5, 0.0]];
+        let sigma = util::sigma_from_gradient(&grad);
+        assert_eq!(sigma, vec![1.0 + 4.0 + 9.0, 0.25 + 0.25]);
+    }
+
+    #[test]
+    fn sigma_from_gradient_polarized() {
+        let up = [[1.0, 2.0, 3.0]];
+        let down = [[2.0, 0.0, -1.0]];
+        let sigma = util::sigma_from_gradient_polarized(&up, &down);
+        assert_eq!(sigma, vec![14.0, 2.0 - 3.0, 5.0]);
     }
 }
@@ -0,0 +1,155 @@
+//! Combinations of functionals evaluated as a single exchange-correlation functional.
+
+use crate::error::FunctionalError;
+use crate::functional::Functional;
+
+/// Sum of several functionals evaluated on the same inputs.
+pub struct Xc {
+    functionals: Vec<Functional>,
+}
+
+impl Xc {
+    /// Constructs an [Xc] from functionals which are compatible with each other.
+    pub fn new(functionals: Vec<Functional>) -> Result<Self, FunctionalError> {
+        let first = match functionals.first() {
+            Some(first) => first,
+            None => return Err(FunctionalError::NoFunctionals),
+        };
+        if !functionals
+            .iter()
+            .all(|func| first.is_compatible_with(func))
+        {
+            return Err(FunctionalError::IncompatibleFunctionals);
+        }
+        Ok(Xc { functionals })
+    }
+
+    /// Returns the combined functionals.
+    pub fn functionals(&self) -> &[Functional] {
+        &self.functionals
+    }
+
+    /// Evaluates the summed LDA energy per particle at each point.
+    pub fn exc(&self, rho: &[f64]) -> Vec<f64> {
+        let (exchange, rest) = self.exc_by_kind(rho);
+        exchange
+            .iter()
+            .zip(rest.iter())
+            .map(|(x, c)| x + c)
+            .collect()
+    }
+
+    /// Evaluates the summed LDA energy per particle separately for exchange and correlation.
+    ///
+    /// Functionals of any kind other than exchange are summed into the correlation part, so the
+    /// two parts always add up to [Xc::exc].
+    pub fn exc_by_kind(&self, rho: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let npoints = rho.len() / self.functionals[0].n_spin();
+        let mut exchange = vec![0.0; npoints];
+        let mut correlation = vec![0.0; npoints];
+        for func in &self.functionals {
+            let part = if func.is_exchange() {
+                &mut exchange
+            } else {
+                &mut correlation
+            };
+            for (total, exc) in part.iter_mut().zip(func.lda_exc(rho)) {
+                *total += exc;
+            }
+        }
+        (exchange, correlation)
+    }
+
+    /// Evaluates the summed GGA energy per particle and first derivatives at each point.
+    pub fn gga_exc_vxc(&self, rho: &[f64], sigma: &[f64]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let npoints = rho.len() / self.functionals[0].n_spin();
+        let mut zk = vec![0.0; npoints];
+        let mut vrho = vec![0.0; rho.len()];
+        let mut vsigma = vec![0.0; sigma.len()];
+        let add = |total: &mut Vec<f64>, part: Vec<f64>| {
+            for (t, p) in total.iter_mut().zip(part) {
+                *t += p;
+            }
+        };
+        for func in &self.functionals {
+            let (part_zk, part_vrho, part_vsigma) = func.gga_exc_vxc(rho, sigma);
+            add(&mut zk, part_zk);
+            add(&mut vrho, part_vrho);
+            add(&mut vsigma, part_vsigma);
+        }
+        (zk, vrho, vsigma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::functional::{Functional, Polarization};
+    use crate::ids;
+    use crate::xc::Xc;
+
+    fn slater_vwn() -> Xc {
+        Xc::new(vec![
+            Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap(),
+            Functional::from_id(ids::LDA_C_VWN, Polarization::Unpolarized).unwrap(),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn new_incompatible() {
+        let xc = Xc::new(vec![
+            Functional::from_id(ids::LDA_X, Polarization::Unpolarized).unwrap(),
+            Functional::from_id(ids::GGA_C_PBE, Polarization::Unpolarized).unwrap(),
+        ]);
+        assert!(xc.is_err());
+        assert!(Xc::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn exc() {
+        let xc = slater_vwn();
+        let rho = [0.1, 0.2, 0.3];
+        let x = xc.functionals()[0].lda_exc(&rho);
+        let c = xc.functionals()[1].lda_exc(&rho);
+        let exc = xc.exc(&rho);
+        for i in 0..3 {
+            assert!((exc[i] - (x[i] + c[i])).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn gga_exc_vxc() {
+        let xc = Xc::new(vec![
+            Functional::from_id(ids::GGA_X_PBE, Polarization::Polarized).unwrap(),
+            Functional::from_id(ids::GGA_C_PBE, Polarization::Polarized).unwrap(),
+        ])
+        .unwrap();
+        let rho = [0.1, 0.2, 0.3, 0.4];
+        let sigma = [0.01, 0.005, 0.02, 0.03, 0.01, 0.04];
+        let (x_zk, x_vrho, x_vsigma) = xc.functionals()[0].gga_exc_vxc(&rho, &sigma);
+        let (c_zk, c_vrho, c_vsigma) = xc.functionals()[1].gga_exc_vxc(&rho, &sigma);
+        let (zk, vrho, vsigma) = xc.gga_exc_vxc(&rho, &sigma);
+        for i in 0..2 {
+            assert!((zk[i] - (x_zk[i] + c_zk[i])).abs() < 1e-14);
+        }
+        for i in 0..4 {
+            assert!((vrho[i] - (x_vrho[i] + c_vrho[i])).abs() < 1e-14);
+        }
+        for i in 0..6 {
+            assert!((vsigma[i] - (x_vsigma[i] + c_vsigma[i])).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn exc_by_kind() {
+        let xc = slater_vwn();
+        let rho = [0.1, 0.2, 0.3];
+        let (exchange, correlation) = xc.exc_by_kind(&rho);
+        assert_eq!(exchange, xc.functionals()[0].lda_exc(&rho));
+        assert_eq!(correlation, xc.functionals()[1].lda_exc(&rho));
+        let exc = xc.exc(&rho);
+        for i in 0..3 {
+            assert!((exchange[i] + correlation[i] - exc[i]).abs() < 1e-14);
+        }
+    }
+}